@@ -13,28 +13,570 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-//! Confirm gexiv2 library exists on the system.
+//! Confirm gexiv2 library exists on the system, and detect which version it is.
+//!
+//! For every entry in `VERSION_CFGS` that the installed gexiv2 is at least as
+//! new as, a `gexiv2_X_Y` cfg flag is set, which `src/lib.rs` uses to gate
+//! declarations on the version that introduced them. The detected version and
+//! include paths are also passed on to dependent crates' build scripts as
+//! `DEP_GEXIV2_VERSION` and `DEP_GEXIV2_INCLUDE`. If pkg-config finds Exiv2
+//! 0.28 or newer, `exiv2_0_28` is set as well, since that release renumbered
+//! the error codes gexiv2 reports.
+//!
+//! gexiv2 0.16 is published under a versioned pkg-config name, so each name in
+//! `PKG_NAMES` is tried in turn, after the one named by `GEXIV2_PKG_NAME` if set.
+//!
+//! With the `vendored` feature, gexiv2 and Exiv2 are instead built from the
+//! sources in the `gexiv2-src` crate and linked statically; pkg-config is then
+//! only used to read the link flags of that build.
+//!
+//! With the `bindgen` feature, declarations are also generated from the
+//! installed headers into `$OUT_DIR/gexiv2.rs`, and the functions declared in
+//! `src/lib.rs` are listed in `$OUT_DIR/functions.rs`, so the tests can check
+//! the hand-written declarations against the generated ones.
+//!
+//! With the `dlopen` feature, nothing is linked either, and `src/dlopen.rs`
+//! builds a struct of pointers to the functions listed in
+//! `$OUT_DIR/functions.rs` that is filled in at runtime. The cfg flags are set
+//! for the installed gexiv2 if pkg-config finds one, or for `STUB_VERSION`
+//! otherwise.
+//!
+//! On docs.rs, or when `GEXIV2_STUB` is set, gexiv2 is not looked for at all:
+//! the cfg flags are set as if `STUB_VERSION` were installed, and neither the
+//! glue nor anything else is compiled or linked. That is enough to type-check
+//! and document this crate and its dependents, but not to link them.
 
+#[cfg(all(feature = "dlopen", feature = "vendored"))]
+compile_error!("the dlopen and vendored features are mutually exclusive");
+
+#[cfg(feature = "bindgen")]
+extern crate bindgen;
 extern crate cc;
+#[cfg(feature = "vendored")]
+extern crate gexiv2_src;
 extern crate pkg_config;
 
+use std::env;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+/// Oldest version of gexiv2 whose API is fully covered by the ungated declarations.
+const MIN_VERSION: &str = "0.10";
+
+include!("version_cfgs.rs");
+
+/// cfg flag set when building against Exiv2 0.28 or newer.
+const EXIV2_0_28_CFG: &str = "exiv2_0_28";
+
+/// pkg-config package names gexiv2 is published under, newest first.
+#[cfg(not(feature = "vendored"))]
+const PKG_NAMES: &[&str] = &["gexiv2-0.16", "gexiv2"];
+
+/// Environment variable naming a pkg-config package to try before `PKG_NAMES`.
+#[cfg(not(feature = "vendored"))]
+const PKG_NAME_ENV: &str = "GEXIV2_PKG_NAME";
+
+/// Environment variable naming the directory holding the gexiv2 library, to use instead of pkg-config.
+#[cfg(not(feature = "vendored"))]
+const LIB_DIR_ENV: &str = "GEXIV2_LIB_DIR";
+
+/// Environment variable listing directories to search for the gexiv2 (and GLib) headers.
+const INCLUDE_DIR_ENV: &str = "GEXIV2_INCLUDE_DIR";
+
+/// Environment variable listing the directories holding the GLib headers, used with `GEXIV2_LIB_DIR`.
+#[cfg(not(feature = "vendored"))]
+const GLIB_INCLUDE_DIR_ENV: &str = "GLIB_INCLUDE_DIR";
+
+/// Environment variable that, if set to anything but `0`, makes gexiv2 and Exiv2 link statically.
+#[cfg(not(feature = "vendored"))]
+const STATIC_ENV: &str = "GEXIV2_STATIC";
+
+/// Environment variable that, if set to anything but `0`, puts the build script into stub mode.
+///
+/// Its value can also name the gexiv2 version to gate the declarations for, like `0.16`.
+const STUB_ENV: &str = "GEXIV2_STUB";
+
+/// Version the declarations are gated for in stub mode: the newest that still has the deprecated ones.
+const STUB_VERSION: &str = "0.14.0";
+
+/// Libraries gexiv2 needs linked dynamically, when not using pkg-config.
+///
+/// They are needed even for a shared gexiv2, since we call into them directly and
+/// linkers do not resolve symbols through the dependencies of other libraries.
+#[cfg(not(feature = "vendored"))]
+const GLIB_LIBRARIES: &[&str] = &["gobject-2.0", "glib-2.0"];
+
+/// Further libraries a statically linked Exiv2 needs linked dynamically, when not using pkg-config.
+#[cfg(not(feature = "vendored"))]
+const STATIC_DEPENDENCIES: &[&str] = &["expat", "z"];
+
+/// The gexiv2 library being built against, however it was found.
+struct Gexiv2 {
+    version: String,
+    include_paths: Vec<PathBuf>,
+    link_paths: Vec<PathBuf>,
+    libs: Vec<String>,
+    statik: bool,
+    /// How the above were found, for the summary printed by `main`.
+    origin: String,
+}
+
 fn main() {
+    println!("cargo:rustc-check-cfg=cfg({})", EXIV2_0_28_CFG);
+    #[cfg(any(feature = "bindgen", feature = "dlopen"))]
+    generate_function_list();
+
+    if let Some(version) = stub_version() {
+        set_version_cfgs(&version);
+        println!("cargo:version={}", version);
+        #[cfg(feature = "bindgen")]
+        write_stub_bindings();
+        println!(
+            "gexiv2-sys: stub mode, declarations gated for gexiv2 {}; nothing is linked",
+            version
+        );
+        return;
+    }
+
+    #[cfg(feature = "dlopen")]
+    set_up_dlopen();
+    #[cfg(not(feature = "dlopen"))]
+    build_and_link();
+}
+
+/// Set the cfg flags for gexiv2 without linking against it, so `src/dlopen.rs` can load it at runtime.
+#[cfg(feature = "dlopen")]
+fn set_up_dlopen() {
+    let found = find_gexiv2(false, false).ok();
+    let version = found
+        .as_ref()
+        .map_or(STUB_VERSION, |gexiv2| gexiv2.version.as_str());
+    set_version_cfgs(version);
+    println!("cargo:version={}", version);
+    #[cfg(feature = "bindgen")]
+    match found {
+        Some(ref gexiv2) => generate_bindings(&gexiv2.include_paths),
+        None => write_stub_bindings(),
+    }
+    println!(
+        "gexiv2-sys: dlopen mode, declarations gated for gexiv2 {}; nothing is linked",
+        version
+    );
+}
+
+/// Find gexiv2, link against it, and compile the glue.
+#[cfg_attr(feature = "dlopen", allow(dead_code))]
+fn build_and_link() {
     let mut cfg = cc::Build::new();
-    let gexiv2_lib = match pkg_config::Config::new().atleast_version("0.15").find("gexiv2") {
-        Ok(lib) => lib,
-        Err(e) => {
-            println!(
-                "\nThe gexiv2 library (at least version 0.15) was not found by pkg-config/pkgconf on your system.\n\n\
-                 Consult the README.md file for suggestions on how to acquire it."
-            );
-            panic!("{}", e);
-        }
+    #[cfg(feature = "vendored")]
+    let mut gexiv2 = build_gexiv2();
+    #[cfg(not(feature = "vendored"))]
+    let mut gexiv2 = match env_var(LIB_DIR_ENV) {
+        Some(lib_dir) => from_lib_dir(PathBuf::from(lib_dir), link_statically())
+            .unwrap_or_else(|e| panic!("{} is set, but {}", LIB_DIR_ENV, e)),
+        None => match find_gexiv2(link_statically(), true) {
+            Ok(lib) => lib,
+            Err(e) => {
+                println!(
+                    "\nThe gexiv2 library (at least version {}) was not found by pkg-config/pkgconf on your system.\n\
+                     Looked for the packages: {} (set {} to look for another one first).\n\
+                     To use a gexiv2 that has no pkg-config file, set {} (and {} if needed) instead.\n\n\
+                     Consult the README.md file for suggestions on how to acquire it.",
+                    MIN_VERSION,
+                    PKG_NAMES.join(", "),
+                    PKG_NAME_ENV,
+                    LIB_DIR_ENV,
+                    INCLUDE_DIR_ENV
+                );
+                panic!("{}", e);
+            }
+        },
     };
+    if let Some(dirs) = env_var(INCLUDE_DIR_ENV) {
+        let mut include_paths: Vec<PathBuf> = env::split_paths(&dirs).collect();
+        include_paths.retain(|path| !gexiv2.include_paths.contains(path));
+        include_paths.append(&mut gexiv2.include_paths);
+        gexiv2.include_paths = include_paths;
+    }
+    print_summary(&gexiv2);
+
     cfg.file("src/glue.cpp");
+    // A statically linked Exiv2 needs the C++ standard library linked in too.
+    cfg.cpp(gexiv2.statik);
+
+    set_version_cfgs(&gexiv2.version);
+    set_exiv2_cfg();
+    println!("cargo:version={}", gexiv2.version);
+    if let Ok(include) = env::join_paths(&gexiv2.include_paths) {
+        println!("cargo:include={}", include.to_string_lossy());
+    }
+
+    #[cfg(feature = "bindgen")]
+    generate_bindings(&gexiv2.include_paths);
 
-    for path in gexiv2_lib.include_paths {
+    for path in gexiv2.include_paths {
         cfg.include(path);
     }
 
     cfg.compile("gexiv2_sys_glue");
 }
+
+/// The version to gate declarations for if in stub mode, i.e. on docs.rs or with `GEXIV2_STUB` set.
+fn stub_version() -> Option<String> {
+    match env_var(STUB_ENV) {
+        Some(value) if value != "0" => Some(
+            value
+                .to_str()
+                .filter(|value| value.contains('.'))
+                .unwrap_or(STUB_VERSION)
+                .to_string(),
+        ),
+        _ => env_var("DOCS_RS").map(|_| STUB_VERSION.to_string()),
+    }
+}
+
+/// Set a cfg flag for each entry in `VERSION_CFGS` the given version is at least as new as.
+fn set_version_cfgs(version: &str) {
+    let version = parse_version(version);
+    for &(introduced, flag) in VERSION_CFGS {
+        println!("cargo:rustc-check-cfg=cfg({})", flag);
+        if version >= introduced {
+            println!("cargo:rustc-cfg={}", flag);
+        }
+    }
+}
+
+/// Set `EXIV2_0_28_CFG` if pkg-config finds Exiv2 0.28 or newer.
+///
+/// Exiv2 0.28 started numbering its error codes from 1 rather than 0, and
+/// gexiv2 passes those codes on in the `GError`s it reports.
+#[cfg_attr(feature = "dlopen", allow(dead_code))]
+fn set_exiv2_cfg() {
+    let found = pkg_config::Config::new()
+        .atleast_version("0.28")
+        .cargo_metadata(false)
+        .env_metadata(false)
+        .probe("exiv2");
+    if found.is_ok() {
+        println!("cargo:rustc-cfg={}", EXIV2_0_28_CFG);
+    }
+}
+
+/// Read an environment variable, and have the build script rerun when it changes.
+fn env_var(name: &str) -> Option<OsString> {
+    println!("cargo:rerun-if-env-changed={}", name);
+    env::var_os(name)
+}
+
+/// Whether `GEXIV2_STATIC` asks for gexiv2 and Exiv2 to be linked statically.
+#[cfg(not(feature = "vendored"))]
+fn link_statically() -> bool {
+    env_var(STATIC_ENV).map_or(false, |value| value != "0")
+}
+
+/// Print what is being built against. Cargo only shows this with `-vv`, or when the build fails.
+fn print_summary(gexiv2: &Gexiv2) {
+    let join = |paths: &[PathBuf]| {
+        paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    println!(
+        "gexiv2-sys: using gexiv2 {} ({})",
+        gexiv2.version, gexiv2.origin
+    );
+    println!(
+        "gexiv2-sys:   include paths: {}",
+        join(&gexiv2.include_paths)
+    );
+    println!("gexiv2-sys:   library paths: {}", join(&gexiv2.link_paths));
+    println!("gexiv2-sys:   libraries: {}", gexiv2.libs.join(", "));
+    println!(
+        "gexiv2-sys:   gexiv2 and Exiv2 linked {}",
+        if gexiv2.statik {
+            "statically"
+        } else {
+            "dynamically"
+        }
+    );
+}
+
+/// Look up gexiv2 via pkg-config, trying each candidate package name in order of preference.
+///
+/// Unless `link` is set, only the version and paths are looked up, and nothing is linked.
+#[cfg(not(feature = "vendored"))]
+fn find_gexiv2(statik: bool, link: bool) -> Result<Gexiv2, String> {
+    println!("cargo:rerun-if-env-changed={}", PKG_NAME_ENV);
+    let preferred = env::var(PKG_NAME_ENV).ok();
+    let candidates = preferred
+        .iter()
+        .map(String::as_str)
+        .chain(PKG_NAMES.iter().cloned());
+
+    let mut first_err = None;
+    for name in candidates {
+        match pkg_config::Config::new()
+            .atleast_version(MIN_VERSION)
+            .statik(statik)
+            .cargo_metadata(link)
+            .find(name)
+        {
+            Ok(lib) => {
+                return Ok(from_pkg_config(
+                    lib,
+                    statik,
+                    format!("pkg-config package {}", name),
+                ))
+            }
+            Err(e) => first_err = first_err.or(Some(e)),
+        }
+    }
+    Err(first_err.expect("at least one package name is always tried"))
+}
+
+/// Build gexiv2 and Exiv2 from the sources in `gexiv2-src`, and link them statically.
+///
+/// The build installs pkg-config files along with the libraries, so pkg-config
+/// is pointed at those to get the include paths and link flags, including those
+/// of the dependencies that are still linked dynamically, like GLib.
+#[cfg(feature = "vendored")]
+fn build_gexiv2() -> Gexiv2 {
+    let artifacts = gexiv2_src::Build::new().build();
+    let mut paths = vec![artifacts.pkg_config_dir()];
+    if let Some(existing) = env::var_os("PKG_CONFIG_PATH") {
+        paths.extend(env::split_paths(&existing));
+    }
+    env::set_var(
+        "PKG_CONFIG_PATH",
+        env::join_paths(paths).expect("PKG_CONFIG_PATH entries are valid paths"),
+    );
+    let lib = pkg_config::Config::new()
+        .statik(true)
+        .atleast_version(MIN_VERSION)
+        .find("gexiv2")
+        .expect("the gexiv2 built from gexiv2-src was not found by pkg-config");
+    from_pkg_config(
+        lib,
+        true,
+        format!("built from {}", gexiv2_src::source_dir().display()),
+    )
+}
+
+/// Keep what pkg-config found; it has already printed the link directives for it.
+fn from_pkg_config(lib: pkg_config::Library, statik: bool, origin: String) -> Gexiv2 {
+    Gexiv2 {
+        version: lib.version,
+        include_paths: lib.include_paths,
+        link_paths: lib.link_paths,
+        libs: lib.libs,
+        statik,
+        origin,
+    }
+}
+
+/// Use the gexiv2 library in the given directory, without consulting pkg-config.
+///
+/// The headers are looked for in the directories listed in `GEXIV2_INCLUDE_DIR`
+/// or, failing that, in the `include` directory next to `lib_dir`, and the
+/// version is read from them. The GLib headers they include are looked for as
+/// described in `glib_include_paths`. `GLIB_LIBRARIES` are always linked dynamically.
+/// When linking statically, Exiv2 is expected in `lib_dir` too, and
+/// `STATIC_DEPENDENCIES` are linked dynamically as well.
+#[cfg(not(feature = "vendored"))]
+fn from_lib_dir(lib_dir: PathBuf, statik: bool) -> Result<Gexiv2, String> {
+    let mut include_paths: Vec<PathBuf> = match env_var(INCLUDE_DIR_ENV) {
+        Some(dirs) => env::split_paths(&dirs).collect(),
+        None => vec![lib_dir.parent().unwrap_or(&lib_dir).join("include")],
+    };
+    let version = header_version(&include_paths)?;
+    for path in glib_include_paths() {
+        if !include_paths.contains(&path) {
+            include_paths.push(path);
+        }
+    }
+
+    let name = if parse_version(&version) >= (0, 15) {
+        "gexiv2-0.16"
+    } else {
+        "gexiv2"
+    };
+    let mut libs = vec![name.to_string()];
+    if statik {
+        libs.push("exiv2".to_string());
+        // Exiv2 0.27 installs its bundled XMP SDK as a separate static library.
+        if lib_dir.join("libexiv2-xmp.a").exists() {
+            libs.push("exiv2-xmp".to_string());
+        }
+    }
+
+    println!("cargo:rustc-link-search=native={}", lib_dir.display());
+    let kind = if statik { "static" } else { "dylib" };
+    for lib in &libs {
+        println!("cargo:rustc-link-lib={}={}", kind, lib);
+    }
+    let dependencies = if statik { STATIC_DEPENDENCIES } else { &[] };
+    for lib in GLIB_LIBRARIES.iter().chain(dependencies) {
+        println!("cargo:rustc-link-lib=dylib={}", lib);
+        libs.push(lib.to_string());
+    }
+
+    Ok(Gexiv2 {
+        version,
+        include_paths,
+        link_paths: vec![lib_dir],
+        libs,
+        statik,
+        origin: format!("{} and {}", LIB_DIR_ENV, INCLUDE_DIR_ENV),
+    })
+}
+
+/// The directories holding the GObject and GLib headers, which gexiv2's headers and the glue include.
+///
+/// They are listed in `GLIB_INCLUDE_DIR` if it is set. Otherwise pkg-config is asked for them,
+/// without linking anything; if it does not know GLib either, only the compiler's default
+/// directories are searched, which normally lack GLib's `glibconfig.h`.
+#[cfg(not(feature = "vendored"))]
+fn glib_include_paths() -> Vec<PathBuf> {
+    if let Some(dirs) = env_var(GLIB_INCLUDE_DIR_ENV) {
+        return env::split_paths(&dirs).collect();
+    }
+    match pkg_config::Config::new()
+        .cargo_metadata(false)
+        .env_metadata(false)
+        .probe("gobject-2.0")
+    {
+        Ok(lib) => lib.include_paths,
+        Err(_) => {
+            println!(
+                "cargo:warning=pkg-config did not find the GLib headers; if compiling fails, set \
+                 {} to the directories holding them.",
+                GLIB_INCLUDE_DIR_ENV
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// Read the gexiv2 version from the `gexiv2/gexiv2-version.h` header in one of the given directories.
+#[cfg(not(feature = "vendored"))]
+fn header_version(include_paths: &[PathBuf]) -> Result<String, String> {
+    let header = include_paths
+        .iter()
+        .map(|dir| dir.join("gexiv2").join("gexiv2-version.h"))
+        .find(|header| header.exists())
+        .ok_or_else(|| {
+            format!(
+                "gexiv2/gexiv2-version.h was not found in any of: {}",
+                env::join_paths(include_paths)
+                    .map(|paths| paths.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            )
+        })?;
+    let contents = std::fs::read_to_string(&header)
+        .map_err(|e| format!("{} could not be read: {}", header.display(), e))?;
+
+    let component = |name: &str| {
+        contents
+            .lines()
+            .filter_map(|line| {
+                line.trim()
+                    .strip_prefix("#define")?
+                    .trim()
+                    .strip_prefix(name)
+            })
+            .map(|value| value.trim().trim_matches(|c| c == '(' || c == ')'))
+            .find(|value| !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()))
+            .ok_or_else(|| format!("{} does not define {}", header.display(), name))
+    };
+    Ok(format!(
+        "{}.{}.{}",
+        component("GEXIV2_MAJOR_VERSION")?,
+        component("GEXIV2_MINOR_VERSION")?,
+        component("GEXIV2_MICRO_VERSION")?
+    ))
+}
+
+/// Generate declarations for everything in the installed gexiv2 headers.
+#[cfg(feature = "bindgen")]
+fn generate_bindings(include_paths: &[std::path::PathBuf]) {
+    let out_dir = std::path::PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR is set by Cargo"));
+    bindgen::Builder::default()
+        .header_contents("gexiv2-sys.h", "#include <gexiv2/gexiv2.h>\n")
+        .clang_args(
+            include_paths
+                .iter()
+                .map(|path| format!("-I{}", path.display())),
+        )
+        .allowlist_function("gexiv2_.*")
+        .allowlist_type("GExiv2.*")
+        .allowlist_var("GEXIV2_.*")
+        .generate()
+        .expect("failed to generate bindings from the gexiv2 headers")
+        .write_to_file(out_dir.join("gexiv2.rs"))
+        .expect("failed to write the generated gexiv2 bindings");
+}
+
+/// Write empty generated declarations, so the test comparing against them still compiles in stub mode.
+#[cfg(feature = "bindgen")]
+fn write_stub_bindings() {
+    let out_dir = std::path::PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR is set by Cargo"));
+    std::fs::write(
+        out_dir.join("gexiv2.rs"),
+        "// Stub mode: no headers to generate declarations from.\n",
+    )
+    .expect("failed to write the stub gexiv2 bindings");
+}
+
+/// List the functions declared in `src/lib.rs` in `$OUT_DIR/functions.rs`, for `src/dlopen.rs` and the tests.
+///
+/// Each becomes a `fn` item in a `functions!` invocation, minus its doc comment
+/// and with the cfg attributes of its `extern` block added to its own.
+#[cfg(any(feature = "bindgen", feature = "dlopen"))]
+fn generate_function_list() {
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    let src = std::fs::read_to_string("src/lib.rs").expect("failed to read src/lib.rs");
+
+    let mut list = String::from("// Generated by build.rs from src/lib.rs.\nfunctions! {\n");
+    let (mut block_attrs, mut item_attrs) = (Vec::new(), Vec::new());
+    let (mut in_block, mut item) = (false, String::new());
+    for line in src.lines().map(str::trim) {
+        if !in_block {
+            if line == "extern \"C\" {" {
+                in_block = true;
+            } else if line.starts_with("#[cfg(") {
+                block_attrs.push(line);
+            } else if !line.starts_with("//") {
+                block_attrs.clear();
+            }
+        } else if line == "}" {
+            in_block = false;
+            block_attrs.clear();
+        } else if line.starts_with("#[") {
+            item_attrs.push(line);
+        } else if !line.starts_with("//") && !line.is_empty() {
+            item.push_str(line);
+            item.push(' ');
+            if line.ends_with(';') {
+                for attr in block_attrs.iter().chain(&item_attrs) {
+                    list.push_str(&format!("    {}\n", attr));
+                }
+                let decl = item.replace("( ", "(").replace(", )", ")");
+                list.push_str(&format!("    {}\n", decl.trim().trim_start_matches("pub ")));
+                item_attrs.clear();
+                item.clear();
+            }
+        }
+    }
+    list.push_str("}\n");
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR is set by Cargo"));
+    std::fs::write(out_dir.join("functions.rs"), list).expect("failed to write the function list");
+}
+
+/// Extract the major and minor components from a version string like "0.14.2".
+fn parse_version(version: &str) -> (u32, u32) {
+    let mut parts = version.split('.').map(|part| part.parse().unwrap_or(0));
+    (parts.next().unwrap_or(0), parts.next().unwrap_or(0))
+}
@@ -0,0 +1,246 @@
+// Copyright © 2015–2022 Felix A. Crux <felixc@felixcrux.com> and contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Generates `src/tags/generated.rs` from a list of tag names.
+//!
+//! gexiv2 cannot enumerate the tags Exiv2 knows, so the names are read from
+//! standard input: any text containing full tag names works, such as the
+//! output of Exiv2's `taglist` sample program. Each name is looked up in the
+//! installed gexiv2 for its label, description and type, and those it does
+//! not know are skipped.
+//!
+//! To run it, try:
+//!   $ taglist all > tags.txt && taglist Iptc >> tags.txt
+//!   $ cargo run --example generate_tags < tags.txt > src/tags/generated.rs
+
+extern crate gexiv2_sys as gexiv2;
+extern crate libc;
+
+#[cfg(not(feature = "dlopen"))]
+mod generate {
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::ffi::CStr;
+    use std::io::{self, Read, Write};
+
+    use gexiv2::error::with_gerror;
+    use gexiv2::tag::TagKey;
+    use gexiv2::GError;
+    use libc::c_char;
+
+    #[cfg(not(gexiv2_0_14))]
+    use gexiv2::{
+        gexiv2_metadata_get_tag_description as get_tag_description,
+        gexiv2_metadata_get_tag_label as get_tag_label,
+        gexiv2_metadata_get_tag_type as get_tag_type,
+    };
+    #[cfg(gexiv2_0_14)]
+    use gexiv2::{
+        gexiv2_metadata_try_get_tag_description as get_tag_description,
+        gexiv2_metadata_try_get_tag_label as get_tag_label,
+        gexiv2_metadata_try_get_tag_type as get_tag_type,
+    };
+
+    /// The license header the other source files in `src/` start with.
+    const LICENSE_HEADER: &str = "\
+        // Copyright © 2015–2022 Felix A. Crux <felixc@felixcrux.com> and contributors\n\
+        //\n\
+        // This program is free software: you can redistribute it and/or modify\n\
+        // it under the terms of the GNU General Public License as published by\n\
+        // the Free Software Foundation, either version 3 of the License, or\n\
+        // (at your option) any later version.\n\
+        //\n\
+        // This program is distributed in the hope that it will be useful,\n\
+        // but WITHOUT ANY WARRANTY; without even the implied warranty of\n\
+        // MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the\n\
+        // GNU General Public License for more details.\n\
+        //\n\
+        // You should have received a copy of the GNU General Public License\n\
+        // along with this program. If not, see <http://www.gnu.org/licenses/>.\n\
+        \n";
+
+    type Lookup = unsafe extern "C" fn(*const c_char, *mut *mut GError) -> *const c_char;
+
+    /// Everything the generated constant records about a tag.
+    struct Info {
+        label: String,
+        description: String,
+        type_name: String,
+    }
+
+    /// Look up one property of a tag, or `None` if gexiv2 does not know the tag.
+    fn lookup(function: Lookup, key: &TagKey) -> Option<String> {
+        let value = unsafe { with_gerror(|err| function(key.as_ptr(), err)) }.ok()?;
+        if value.is_null() {
+            return None;
+        }
+        Some(
+            unsafe { CStr::from_ptr(value) }
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+
+    fn info(key: &TagKey) -> Option<Info> {
+        Some(Info {
+            label: lookup(get_tag_label, key)?,
+            description: lookup(get_tag_description, key).unwrap_or_default(),
+            type_name: lookup(get_tag_type, key).unwrap_or_default(),
+        })
+    }
+
+    /// Split a tag name part like `GPSLatitudeRef` into upper-case words: `GPS_LATITUDE_REF`.
+    fn screaming_snake(part: &str) -> String {
+        let chars: Vec<char> = part.chars().collect();
+        let mut out = String::new();
+        for (i, &c) in chars.iter().enumerate() {
+            if !c.is_ascii_alphanumeric() {
+                out.push('_');
+                continue;
+            }
+            if i > 0 && c.is_ascii_uppercase() {
+                let prev = chars[i - 1];
+                let next_lower = matches!(chars.get(i + 1), Some(n) if n.is_ascii_lowercase());
+                if prev.is_ascii_lowercase()
+                    || prev.is_ascii_digit()
+                    || (prev.is_ascii_uppercase() && next_lower)
+                {
+                    out.push('_');
+                }
+            }
+            out.push(c.to_ascii_uppercase());
+        }
+        out
+    }
+
+    /// The constant name for a tag, e.g. `EXIF_PHOTO_DATE_TIME_ORIGINAL`.
+    ///
+    /// Names that only differ in case, like `ISOSpeed` and `IsoSpeed`, would map to the same
+    /// words; the later ones fall back to upper-casing the parts without splitting them, and
+    /// then to a numeric suffix.
+    fn constant_name(key: &TagKey, taken: &BTreeSet<String>) -> String {
+        let split = [key.family().as_str(), key.group(), key.name()]
+            .iter()
+            .map(|part| screaming_snake(part))
+            .collect::<Vec<_>>()
+            .join("_");
+        if !taken.contains(&split) {
+            return split;
+        }
+        let unsplit = key
+            .as_str()
+            .replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+            .to_ascii_uppercase();
+        if !taken.contains(&unsplit) {
+            return unsplit;
+        }
+        (2..)
+            .map(|n| format!("{}_{}", unsplit, n))
+            .find(|candidate| !taken.contains(candidate))
+            .expect("some suffix is free")
+    }
+
+    pub fn run() -> io::Result<()> {
+        let mut input = String::new();
+        io::stdin().read_to_string(&mut input)?;
+
+        gexiv2::ensure_initialized();
+        let mut tags = BTreeMap::new();
+        let separators = |c: char| c.is_whitespace() || c == ',' || c == ';' || c == '"';
+        for word in input.split(separators) {
+            // Array items and struct fields are paths into a tag, not tags of their own.
+            if word.contains(&['[', '/'][..]) {
+                continue;
+            }
+            let key = match TagKey::new(word) {
+                Ok(key) => key,
+                Err(_) => continue,
+            };
+            // Exiv2 names tags it has no name for after their number.
+            if key.name().starts_with("0x") || tags.contains_key(key.as_str()) {
+                continue;
+            }
+            if let Some(info) = info(&key) {
+                tags.insert(key.as_str().to_string(), (key, info));
+            }
+        }
+
+        let version = unsafe { gexiv2::gexiv2_get_version() };
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+        write!(out, "{}", LICENSE_HEADER)?;
+        writeln!(
+            out,
+            "// Generated by `cargo run --example generate_tags`; see `src/tags.rs`."
+        )?;
+        writeln!(
+            out,
+            "// Looked up in gexiv2 {}.{}.{}. Do not edit by hand.",
+            version / 10000,
+            version / 100 % 100,
+            version % 100
+        )?;
+        writeln!(out)?;
+        writeln!(out, "use super::TagInfo;")?;
+
+        let mut taken = BTreeSet::new();
+        let mut constants = Vec::new();
+        for (key, info) in tags.values() {
+            let constant = constant_name(key, &taken);
+            taken.insert(constant.clone());
+            constants.push(constant.clone());
+
+            writeln!(out)?;
+            writeln!(out, "/// `{}`: {}", key, info.label)?;
+            if !info.description.is_empty() {
+                writeln!(out, "///")?;
+                for line in info.description.lines() {
+                    writeln!(out, "/// {}", line.trim_end())?;
+                }
+            }
+            if !info.type_name.is_empty() {
+                writeln!(out, "///")?;
+                writeln!(out, "/// Type: `{}`.", info.type_name)?;
+            }
+            writeln!(out, "pub const {}: TagInfo = TagInfo {{", constant)?;
+            writeln!(out, "    name: {:?},", key.as_str())?;
+            writeln!(out, "    label: {:?},", info.label)?;
+            writeln!(out, "    description: {:?},", info.description)?;
+            writeln!(out, "    type_name: {:?},", info.type_name)?;
+            writeln!(out, "}};")?;
+        }
+
+        writeln!(out)?;
+        writeln!(out, "/// Every tag above, sorted by name.")?;
+        writeln!(out, "pub const ALL: &[TagInfo] = &[")?;
+        for constant in constants {
+            writeln!(out, "    {},", constant)?;
+        }
+        writeln!(out, "];")?;
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "dlopen"))]
+fn main() -> std::io::Result<()> {
+    generate::run()
+}
+
+#[cfg(feature = "dlopen")]
+fn main() {
+    eprintln!(
+        "generate_tags looks tags up in a linked gexiv2; build it without the dlopen feature"
+    );
+    std::process::exit(1);
+}
@@ -43,5 +43,7 @@ fn main() {
             println!("{:?}", mime_type);
             cur_offset += 1;
         }
+
+        gexiv2::gexiv2_metadata_free(meta);
     }
 }
@@ -5,13 +5,14 @@
 
 mod open_buf;
 
-#[cfg(feature = "raw-tag-access")]
+#[cfg(all(feature = "raw-tag-access", gexiv2_0_14))]
 mod example {
     extern crate gexiv2_sys as gexiv2;
     extern crate glib_sys as glib;
     extern crate libc;
 
     use std::ffi;
+    use std::ptr;
     use std::slice;
     use std::str;
 
@@ -21,9 +22,15 @@ mod example {
 
             let tag = ffi::CString::new("Exif.Image.ImageDescription").unwrap();
             let tag_value = ffi::CString::new("Raw Tag Access Example").unwrap();
-            gexiv2::gexiv2_metadata_set_tag_string(metadata, tag.as_ptr(), tag_value.as_ptr());
+            gexiv2::gexiv2_metadata_try_set_tag_string(
+                metadata,
+                tag.as_ptr(),
+                tag_value.as_ptr(),
+                ptr::null_mut(),
+            );
 
-            let raw_tag_struct = gexiv2::gexiv2_metadata_get_tag_raw(metadata, tag.as_ptr());
+            let raw_tag_struct =
+                gexiv2::gexiv2_metadata_try_get_tag_raw(metadata, tag.as_ptr(), ptr::null_mut());
 
             let mut raw_tag_buffer_size: usize = 0;
             let raw_tag_buffer =
@@ -32,16 +39,18 @@ mod example {
             let raw_tag_value = slice::from_raw_parts(raw_tag_buffer, raw_tag_buffer_size);
             println!("{:?}", str::from_utf8(raw_tag_value));
 
+            glib::g_free(raw_tag_buffer as glib::gpointer);
             gexiv2::gexiv2_metadata_free(metadata);
         }
     }
 }
 
-#[cfg(not(feature = "raw-tag-access"))]
+#[cfg(not(all(feature = "raw-tag-access", gexiv2_0_14)))]
 mod example {
     pub fn example() {
         println!("You have not enabled the 'raw-tag-access' feature!");
         println!("Try the --features raw-tag-access argument to Cargo.");
+        println!("(The example also needs gexiv2 0.14 or newer.)");
     }
 }
 
@@ -5,12 +5,13 @@
 
 mod open_buf;
 
-#[cfg(feature = "xmp-packet-access")]
+#[cfg(all(feature = "xmp-packet-access", gexiv2_0_14))]
 mod example {
     extern crate gexiv2_sys as gexiv2;
     extern crate libc;
 
     use std::ffi;
+    use std::ptr;
 
     pub fn example() {
         unsafe {
@@ -18,28 +19,37 @@ mod example {
 
             let tag = ffi::CString::new("Xmp.dc.title").unwrap();
             let tag_value = ffi::CString::new("Example").unwrap();
-            gexiv2::gexiv2_metadata_set_tag_string(metadata, tag.as_ptr(), tag_value.as_ptr());
+            gexiv2::gexiv2_metadata_try_set_tag_string(
+                metadata,
+                tag.as_ptr(),
+                tag_value.as_ptr(),
+                ptr::null_mut(),
+            );
 
-            gexiv2::gexiv2_metadata_generate_xmp_packet(
+            let generated = gexiv2::gexiv2_metadata_try_generate_xmp_packet(
                 metadata,
                 (gexiv2::GExiv2XmpFormatFlags::OMIT_PACKET_WRAPPER
                     | gexiv2::GExiv2XmpFormatFlags::OMIT_ALL_FORMATTING)
-                    .bits(),
+                    .bits() as libc::c_uint,
                 1,
+                ptr::null_mut(),
             );
-            let packet = gexiv2::gexiv2_metadata_get_xmp_packet(metadata);
-            println!("{}", ffi::CStr::from_ptr(packet).to_str().unwrap());
+            gexiv2::strings::take_string(generated);
+
+            let packet = gexiv2::gexiv2_metadata_try_get_xmp_packet(metadata, ptr::null_mut());
+            println!("{}", gexiv2::strings::take_string(packet).unwrap());
 
             gexiv2::gexiv2_metadata_free(metadata);
         }
     }
 }
 
-#[cfg(not(feature = "xmp-packet-access"))]
+#[cfg(not(all(feature = "xmp-packet-access", gexiv2_0_14)))]
 mod example {
     pub fn example() {
         println!("You have not enabled the 'xmp_packet_access' feature!");
         println!("Try the --features xmp_packet_access argument to Cargo.");
+        println!("(The example also needs gexiv2 0.14 or newer.)");
     }
 }
 
@@ -0,0 +1,36 @@
+// Copyright © 2015-2022 Felix A. Crux <felixc@felixcrux.com> and contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Warn when the gexiv2 and Exiv2 sources are missing.
+//!
+//! They are Git submodules, so a checkout that has not fetched them still
+//! builds this crate, but `Build::build` has nothing to build from and fails.
+//! Saying so here makes that visible before anything calls it, e.g. when
+//! building the whole workspace or packaging the crate.
+
+use std::path::Path;
+
+fn main() {
+    for (dir, file) in &[("exiv2", "CMakeLists.txt"), ("gexiv2", "meson.build")] {
+        println!("cargo:rerun-if-changed={}/{}", dir, file);
+        if !Path::new(dir).join(file).exists() {
+            println!(
+                "cargo:warning=The {} sources are missing from gexiv2-src/{}, so the vendored \
+                 build will fail; run `git submodule update --init` to fetch them.",
+                dir, dir
+            );
+        }
+    }
+}
@@ -0,0 +1,191 @@
+// Copyright © 2015-2022 Felix A. Crux <felixc@felixcrux.com> and contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Build gexiv2 and Exiv2 from the sources included in this crate.
+//!
+//! This is meant to be called from a build script, as the `vendored` feature of
+//! gexiv2-sys does:
+//!
+//! ```no_run
+//! let artifacts = gexiv2_src::Build::new().build();
+//! println!("{}", artifacts.pkg_config_dir().display());
+//! ```
+//!
+//! Exiv2 is configured with CMake and gexiv2 with Meson; both are installed as
+//! static libraries into one prefix, along with their headers and pkg-config
+//! files. Linking is left to the caller, which can find everything it needs by
+//! pointing pkg-config at [`Artifacts::pkg_config_dir`].
+
+extern crate cmake;
+
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Version of the included Exiv2 sources.
+pub const EXIV2_VERSION: &str = "0.28.3";
+
+/// Version of the included gexiv2 sources.
+pub const GEXIV2_VERSION: &str = "0.14.3";
+
+/// Directory holding the `exiv2` and `gexiv2` source trees.
+pub fn source_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).to_path_buf()
+}
+
+/// Configuration of a build of the included sources.
+#[derive(Debug, Default)]
+pub struct Build {
+    out_dir: Option<PathBuf>,
+}
+
+/// Where a finished build installed its libraries and headers.
+#[derive(Debug)]
+pub struct Artifacts {
+    install_dir: PathBuf,
+}
+
+impl Build {
+    /// Create a build that puts its output under `$OUT_DIR/gexiv2-src`.
+    pub fn new() -> Build {
+        Build {
+            out_dir: env::var_os("OUT_DIR").map(|dir| PathBuf::from(dir).join("gexiv2-src")),
+        }
+    }
+
+    /// Put the build output under the given directory instead.
+    pub fn out_dir<P: AsRef<Path>>(&mut self, path: P) -> &mut Build {
+        self.out_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Build and install Exiv2, then gexiv2 against it.
+    ///
+    /// Panics if the sources are missing or either build fails, since there is
+    /// nothing a build script could do about that.
+    pub fn build(&mut self) -> Artifacts {
+        let out_dir = self
+            .out_dir
+            .clone()
+            .expect("OUT_DIR is not set; call out_dir() when not running in a build script");
+        let install_dir = out_dir.join("install");
+        let exiv2_src = source_dir().join("exiv2");
+        let gexiv2_src = source_dir().join("gexiv2");
+        for (src, file) in &[(&exiv2_src, "CMakeLists.txt"), (&gexiv2_src, "meson.build")] {
+            if !src.join(file).exists() {
+                panic!(
+                    "The sources to build are missing from {}; run `git submodule update --init` \
+                     to fetch them.",
+                    src.display()
+                );
+            }
+        }
+
+        cmake::Config::new(&exiv2_src)
+            .out_dir(out_dir.join("exiv2"))
+            .define("CMAKE_INSTALL_PREFIX", &install_dir)
+            .define("CMAKE_INSTALL_LIBDIR", "lib")
+            .define("CMAKE_POSITION_INDEPENDENT_CODE", "ON")
+            .define("BUILD_SHARED_LIBS", "OFF")
+            .define("EXIV2_ENABLE_XMP", "ON")
+            .define("EXIV2_ENABLE_NLS", "OFF")
+            .define("EXIV2_ENABLE_INIH", "OFF")
+            .define("EXIV2_ENABLE_BROTLI", "OFF")
+            .define("EXIV2_ENABLE_WEBREADY", "OFF")
+            .define("EXIV2_ENABLE_CURL", "OFF")
+            .define("EXIV2_BUILD_SAMPLES", "OFF")
+            .define("EXIV2_BUILD_EXIV2_COMMAND", "OFF")
+            .define("EXIV2_BUILD_UNIT_TESTS", "OFF")
+            .define("EXIV2_BUILD_DOC", "OFF")
+            .build();
+
+        let artifacts = Artifacts { install_dir };
+        let gexiv2_build = out_dir.join("gexiv2");
+        if !gexiv2_build.join("build.ninja").exists() {
+            let mut setup = meson(&artifacts);
+            setup
+                .arg("setup")
+                .arg(&gexiv2_build)
+                .arg(&gexiv2_src)
+                .arg(format!("--prefix={}", artifacts.install_dir.display()))
+                .args([
+                    "--libdir=lib",
+                    "--buildtype=release",
+                    "--default-library=static",
+                    "-Dintrospection=false",
+                    "-Dvapi=false",
+                    "-Dpython3=false",
+                    "-Dtools=false",
+                    "-Dgtk_doc=false",
+                ]);
+            run(&mut setup);
+        }
+        run(meson(&artifacts)
+            .arg("install")
+            .arg("-C")
+            .arg(&gexiv2_build));
+
+        artifacts
+    }
+}
+
+impl Artifacts {
+    /// Prefix everything was installed into.
+    pub fn install_dir(&self) -> &Path {
+        &self.install_dir
+    }
+
+    /// Directory holding the `gexiv2/` and `exiv2/` header directories.
+    pub fn include_dir(&self) -> PathBuf {
+        self.install_dir.join("include")
+    }
+
+    /// Directory holding the static libraries.
+    pub fn lib_dir(&self) -> PathBuf {
+        self.install_dir.join("lib")
+    }
+
+    /// Directory holding the `gexiv2.pc` and `exiv2.pc` pkg-config files.
+    pub fn pkg_config_dir(&self) -> PathBuf {
+        self.lib_dir().join("pkgconfig")
+    }
+}
+
+/// A `meson` command that finds the freshly installed Exiv2 before any other.
+fn meson(artifacts: &Artifacts) -> Command {
+    let mut paths = vec![artifacts.pkg_config_dir()];
+    if let Some(existing) = env::var_os("PKG_CONFIG_PATH") {
+        paths.extend(env::split_paths(&existing));
+    }
+    let mut command = Command::new("meson");
+    command.env(
+        "PKG_CONFIG_PATH",
+        env::join_paths(paths).unwrap_or_else(|_| OsString::from(artifacts.pkg_config_dir())),
+    );
+    command
+}
+
+fn run(command: &mut Command) {
+    println!("running: {:?}", command);
+    match command.status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => panic!("{:?} failed with {}", command, status),
+        Err(e) => panic!(
+            "failed to run {:?}: {}; is Meson installed and on the PATH?",
+            command, e
+        ),
+    }
+}
@@ -0,0 +1,182 @@
+// Copyright © 2015–2022 Felix A. Crux <felixc@felixcrux.com> and contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Loading gexiv2 at runtime rather than linking against it.
+//!
+//! With the `dlopen` feature, nothing is linked against gexiv2, so a program
+//! still starts on systems without it and can treat metadata support as
+//! optional. [`Gexiv2`](struct.Gexiv2.html) holds a pointer to each function
+//! declared at the crate root, under the same name and with the same types:
+//!
+//! ```no_run
+//! # extern crate gexiv2_sys as gexiv2;
+//! match unsafe { gexiv2::dlopen::Gexiv2::load() } {
+//!     Ok(lib) => println!("gexiv2 version {}", unsafe { (lib.gexiv2_get_version)() }),
+//!     Err(e) => println!("metadata support disabled: {}", e),
+//! }
+//! ```
+//!
+//! The crate-root functions themselves must not be called in this mode, since
+//! nothing provides them. The modules that call them, like `safe` and `tag`, and
+//! `ensure_initialized()` are left out, so using those fails to compile.
+
+use std::error;
+use std::ffi::OsStr;
+use std::fmt;
+use std::iter;
+
+use libloading::Library;
+
+use super::*;
+
+/// File names gexiv2 is installed under, tried in turn by [`Gexiv2::load()`](struct.Gexiv2.html#method.load).
+#[cfg(all(unix, not(target_os = "macos"), gexiv2_0_16))]
+pub const LIBRARY_NAMES: &[&str] = &["libgexiv2-0.16.so"];
+/// File names gexiv2 is installed under, tried in turn by [`Gexiv2::load()`](struct.Gexiv2.html#method.load).
+#[cfg(all(unix, not(target_os = "macos"), not(gexiv2_0_16)))]
+pub const LIBRARY_NAMES: &[&str] = &["libgexiv2.so.2", "libgexiv2.so"];
+/// File names gexiv2 is installed under, tried in turn by [`Gexiv2::load()`](struct.Gexiv2.html#method.load).
+#[cfg(target_os = "macos")]
+pub const LIBRARY_NAMES: &[&str] = &["libgexiv2.2.dylib", "libgexiv2.dylib"];
+/// File names gexiv2 is installed under, tried in turn by [`Gexiv2::load()`](struct.Gexiv2.html#method.load).
+#[cfg(windows)]
+pub const LIBRARY_NAMES: &[&str] = &["libgexiv2-2.dll", "gexiv2.dll"];
+
+/// Functions to look up under another name if the loaded library lacks them.
+///
+/// gexiv2 0.16 dropped the free functions that `src/glue.cpp` otherwise provides;
+/// `g_object_unref()`, found through gexiv2's own dependencies, does the same.
+const FALLBACKS: &[(&str, &str)] = &[
+    ("gexiv2_metadata_free", "g_object_unref"),
+    ("gexiv2_preview_image_free", "g_object_unref"),
+];
+
+/// Why gexiv2 could not be loaded.
+#[derive(Debug)]
+pub enum Error {
+    /// The library itself could not be found or loaded.
+    Load(libloading::Error),
+    /// The library was loaded, but lacks these functions, e.g. because it is an older version.
+    MissingSymbols(Vec<String>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Load(ref e) => write!(f, "could not load gexiv2: {}", e),
+            Error::MissingSymbols(ref names) => write!(
+                f,
+                "the loaded gexiv2 lacks these functions: {}",
+                names.join(", ")
+            ),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Load(ref e) => Some(e),
+            Error::MissingSymbols(_) => None,
+        }
+    }
+}
+
+/// Define `Gexiv2` from the function list `build.rs` extracts from `src/lib.rs`.
+macro_rules! functions {
+    ($($(#[$attr:meta])* fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;)*) => {
+        /// The gexiv2 library, loaded at runtime.
+        ///
+        /// Each field points to the function of the same name declared at the crate
+        /// root, and is only present when that declaration is. The library stays
+        /// loaded for as long as this is alive, so the pointers must not be called
+        /// after it is dropped.
+        pub struct Gexiv2 {
+            $($(#[$attr])* pub $name: unsafe extern "C" fn($($ty),*) $(-> $ret)?,)*
+            _library: Library,
+        }
+
+        impl Gexiv2 {
+            unsafe fn from_library(library: Library) -> Result<Gexiv2, Error> {
+                let mut missing = Vec::new();
+                $($(#[$attr])* let $name = symbol(&library, stringify!($name), &mut missing);)*
+                if !missing.is_empty() {
+                    return Err(Error::MissingSymbols(missing));
+                }
+                Ok(Gexiv2 {
+                    $($(#[$attr])* $name: $name.expect("missing functions were reported above"),)*
+                    _library: library,
+                })
+            }
+        }
+    };
+}
+
+include!(concat!(env!("OUT_DIR"), "/functions.rs"));
+
+impl Gexiv2 {
+    /// Load gexiv2 from the first of `LIBRARY_NAMES` that can be found.
+    ///
+    /// If none can, the error is the one for the first name.
+    ///
+    /// # Safety
+    ///
+    /// Loading a library runs its initialisation code, and the functions found in it
+    /// are assumed to have the declared types, which only holds for gexiv2 itself.
+    pub unsafe fn load() -> Result<Gexiv2, Error> {
+        let mut first_err = None;
+        for name in LIBRARY_NAMES {
+            match Library::new(name) {
+                Ok(library) => return Gexiv2::from_library(library),
+                Err(e) => first_err = first_err.or(Some(e)),
+            }
+        }
+        Err(Error::Load(
+            first_err.expect("at least one library name is always tried"),
+        ))
+    }
+
+    /// Load gexiv2 from the given file name or path.
+    ///
+    /// # Safety
+    ///
+    /// As for [`load()`](#method.load), the file must be gexiv2.
+    pub unsafe fn open<P: AsRef<OsStr>>(path: P) -> Result<Gexiv2, Error> {
+        Gexiv2::from_library(Library::new(path).map_err(Error::Load)?)
+    }
+}
+
+impl fmt::Debug for Gexiv2 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Gexiv2")
+            .field("library", &self._library)
+            .finish()
+    }
+}
+
+/// Look up a function by name, or record it as missing.
+unsafe fn symbol<T: Copy>(library: &Library, name: &str, missing: &mut Vec<String>) -> Option<T> {
+    let fallback = FALLBACKS
+        .iter()
+        .find(|&&(function, _)| function == name)
+        .map(|&(_, fallback)| fallback);
+    for candidate in iter::once(name).chain(fallback) {
+        if let Ok(function) = library.get::<T>(candidate.as_bytes()) {
+            return Some(*function);
+        }
+    }
+    missing.push(name.to_string());
+    None
+}
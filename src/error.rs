@@ -0,0 +1,140 @@
+// Copyright © 2015–2022 Felix A. Crux <felixc@felixcrux.com> and contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Owned, typed errors built from the `GError`s gexiv2 reports.
+//!
+//! Functions that take an `error: *mut *mut GError` out-parameter hand the
+//! caller ownership of any `GError` they set, which must then be freed.
+//! [`with_gerror()`](fn.with_gerror.html) takes care of this for any such call:
+//!
+//! ```no_run
+//! # extern crate gexiv2_sys as gexiv2;
+//! # use std::ffi::CString;
+//! let path = CString::new("photo.jpg").unwrap();
+//! let opened = unsafe {
+//!     let meta = gexiv2::gexiv2_metadata_new();
+//!     gexiv2::error::with_gerror(|err| gexiv2::gexiv2_metadata_open_path(meta, path.as_ptr(), err))
+//! };
+//! if let Err(e) = opened {
+//!     println!("{} error {}: {}", e.domain(), e.code(), e);
+//! }
+//! ```
+
+use std::error;
+use std::ffi::{CStr, NulError};
+use std::fmt;
+use std::ptr;
+
+use super::libc::c_char;
+use super::{g_error_free, g_quark_to_string, GError};
+
+/// Domain given to errors that arise in this crate rather than in gexiv2 itself.
+const CRATE_DOMAIN: &str = "gexiv2-sys";
+
+/// An error reported by gexiv2, with its domain resolved and message copied out.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Error {
+    domain: String,
+    code: i32,
+    message: String,
+}
+
+impl Error {
+    /// Take ownership of a `GError`, copying out its contents and then freeing it.
+    ///
+    /// # Safety
+    ///
+    /// `err` must be a valid, non-null pointer to a `GError` that the caller owns. It is freed
+    /// by this call and must not be used afterwards.
+    pub unsafe fn from_raw(err: *mut GError) -> Error {
+        let GError {
+            domain,
+            code,
+            message,
+        } = *err;
+        let error = Error {
+            domain: to_string(g_quark_to_string(domain)),
+            code,
+            message: to_string(message),
+        };
+        g_error_free(err);
+        error
+    }
+
+    /// Create an error for a failure detected in this crate rather than reported by gexiv2.
+    pub(crate) fn other<S: Into<String>>(message: S) -> Error {
+        Error {
+            domain: CRATE_DOMAIN.to_string(),
+            code: 0,
+            message: message.into(),
+        }
+    }
+
+    /// Name of the part of the program from which the error originated, e.g. "GExiv2".
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// Identifier for the error that occurred, specific to its domain.
+    pub fn code(&self) -> i32 {
+        self.code
+    }
+
+    /// Human-readable description of the problem.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<NulError> for Error {
+    fn from(_: NulError) -> Error {
+        Error::other("string argument contains an interior NUL byte")
+    }
+}
+
+/// Run a call that reports failures through a `GError` out-parameter.
+///
+/// Returns the call's result if it left the error unset, and the error otherwise. Any `GError`
+/// set by the call is freed.
+///
+/// # Safety
+///
+/// On return, `call` must have left its argument either null or pointing to a valid `GError`
+/// owned by the caller, as gexiv2 functions do.
+pub unsafe fn with_gerror<T, F: FnOnce(*mut *mut GError) -> T>(call: F) -> Result<T, Error> {
+    let mut err: *mut GError = ptr::null_mut();
+    let result = call(&mut err);
+    if err.is_null() {
+        Ok(result)
+    } else {
+        Err(Error::from_raw(err))
+    }
+}
+
+unsafe fn to_string(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(ptr).to_string_lossy().into_owned()
+    }
+}
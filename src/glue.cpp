@@ -1,17 +1,24 @@
 #include <gexiv2/gexiv2.h>
 #include <glib-object.h>
 
+// gexiv2 0.16 (and its 0.15 development series) dropped the explicit free functions
+// in favour of g_object_unref(), so we provide them ourselves to keep the same
+// declarations working across versions.
+#ifdef GEXIV2_CHECK_VERSION
+#if GEXIV2_CHECK_VERSION(0, 15, 0)
+#define GEXIV2_SYS_PROVIDE_FREE_FUNCTIONS
+#endif
+#endif
+
 extern "C" {
 
-#ifndef gexiv2_metadata_free
+#ifdef GEXIV2_SYS_PROVIDE_FREE_FUNCTIONS
   void gexiv2_metadata_free (GExiv2Metadata *self) {
     g_return_if_fail(GEXIV2_IS_METADATA(self));
 
     g_object_unref(self);
   }
-#endif
 
-#ifndef gexiv2_preview_image_free
   void gexiv2_preview_image_free(GExiv2PreviewImage *self) {
     g_return_if_fail(GEXIV2_IS_PREVIEW_IMAGE(self));
 
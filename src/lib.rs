@@ -21,35 +21,121 @@
 //!
 //! Only FFI declarations are provided here; for a usable Rust library,
 //! consider the rexiv2 crate.
+//!
+//! Declarations that only exist in newer versions of gexiv2 are gated on
+//! `gexiv2_X_Y` cfg flags, which the build script sets according to the
+//! version of gexiv2 it finds installed.
 
 #![crate_type = "lib"]
 
 extern crate libc;
 
-use self::libc::{c_char, c_double, c_int, c_long, c_uchar, c_uint};
+use self::libc::{c_char, c_double, c_int, c_long, c_uchar, c_uint, c_void};
+
+pub mod stream;
+
+// These modules call the crate-root functions, which nothing provides with `dlopen`.
+#[cfg(not(feature = "dlopen"))]
+pub mod error;
+#[cfg(not(feature = "dlopen"))]
+pub mod logging;
+#[cfg(not(feature = "dlopen"))]
+pub mod strings;
+#[cfg(not(feature = "dlopen"))]
+pub mod tag;
+#[cfg(not(feature = "dlopen"))]
+pub mod tags;
+#[cfg(not(feature = "dlopen"))]
+pub mod threads;
+
+#[cfg(feature = "log")]
+extern crate log;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+
+#[cfg(all(feature = "safe", not(feature = "dlopen")))]
+pub mod safe;
+
+#[cfg(feature = "dlopen")]
+extern crate libloading;
+#[cfg(feature = "dlopen")]
+pub mod dlopen;
+
+#[cfg(feature = "gobject")]
+extern crate gobject_sys as gobject;
 
 /// An opaque structure that serves as a container for a media file's metadata.
 ///
 /// You can only create one via [`gexiv2_metadata_new()`](fn.gexiv2_metadata_new.html).
 ///
 /// Be sure to free it after use with [`gexiv2_metadata_free()`](fn.gexiv2_metadata_free.html).
+#[cfg(not(feature = "gobject"))]
 pub enum GExiv2Metadata {}
 
+/// A `GObject` that serves as a container for a media file's metadata.
+///
+/// You can only create one via [`gexiv2_metadata_new()`](fn.gexiv2_metadata_new.html).
+///
+/// Be sure to release it after use, with either `g_object_unref()` or
+/// [`gexiv2_metadata_free()`](fn.gexiv2_metadata_free.html).
+#[cfg(feature = "gobject")]
+#[repr(C)]
+#[derive(Debug)]
+pub struct GExiv2Metadata {
+    pub parent_instance: gobject::GObject,
+    priv_: *mut c_void,
+}
+
 /// An opaque container structure for information about a media file preview image.
 ///
 /// You can only get hold of one (or, rather, a null-terminated array of them) via
 /// [`gexiv2_metadata_get_preview_properties()`](fn.gexiv2_metadata_get_preview_properties.html).
+#[cfg(not(feature = "gobject"))]
 pub enum GExiv2PreviewProperties {}
 
+/// A `GObject` holding information about a media file preview image.
+///
+/// You can only get hold of one (or, rather, a null-terminated array of them) via
+/// [`gexiv2_metadata_get_preview_properties()`](fn.gexiv2_metadata_get_preview_properties.html).
+#[cfg(feature = "gobject")]
+#[repr(C)]
+#[derive(Debug)]
+pub struct GExiv2PreviewProperties {
+    pub parent_instance: gobject::GObject,
+    priv_: *mut c_void,
+}
+
 /// An opaque container structure for a media file preview image.
 ///
 /// You can only get one via
 /// [`gexiv2_metadata_get_preview_image()`](fn.gexiv2_metadata_get_preview_image.html).
 ///
 /// Be sure to free it with [`gexiv2_preview_image_free()`](fn.gexiv2_preview_image_free.html).
+#[cfg(not(feature = "gobject"))]
 pub enum GExiv2PreviewImage {}
 
+/// A `GObject` holding a media file preview image.
+///
+/// You can only get one via
+/// [`gexiv2_metadata_get_preview_image()`](fn.gexiv2_metadata_get_preview_image.html).
+///
+/// Be sure to release it, with either `g_object_unref()` or
+/// [`gexiv2_preview_image_free()`](fn.gexiv2_preview_image_free.html).
+#[cfg(feature = "gobject")]
+#[repr(C)]
+#[derive(Debug)]
+pub struct GExiv2PreviewImage {
+    pub parent_instance: gobject::GObject,
+    priv_: *mut c_void,
+}
+
+/// Identifier of a type registered with the GLib type system.
+pub type GType = libc::size_t;
+
 /// Container for information about recoverable runtime errors.
+///
+/// Any `GError` set by gexiv2 is owned by the caller; converting it into an
+/// [`error::Error`](error/struct.Error.html) frees it.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct GError {
@@ -89,44 +175,197 @@ pub enum GExiv2LogLevel {
     MUTE,
 }
 
+/// Kinds of XMP structure (array) that can be created to hold other XMP values.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum GExiv2StructureType {
+    None = 0,
+    Alt = 20,
+    Bag = 21,
+    Seq = 22,
+    Lang = 23,
+}
+
 /// Handler function that receives gexiv2 log messages and processes them as desired.
 pub type GExiv2LogHandler = extern "C" fn(level: GExiv2LogLevel, msg: *const c_char);
 
+/// Byte orders in which Exif data can be serialized.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum GExiv2ByteOrder {
+    Little,
+    Big,
+}
+
+/// Reference points for seeking within a managed stream.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum WrapperSeekOrigin {
+    Begin,
+    Current,
+    End,
+}
+
+/// Callback reporting whether the stream supports seeking.
+pub type GExiv2StreamCanSeek = extern "C" fn(handle: *mut c_void) -> c_int;
+/// Callback reporting whether the stream supports reading.
+pub type GExiv2StreamCanRead = extern "C" fn(handle: *mut c_void) -> c_int;
+/// Callback reporting whether the stream supports writing.
+pub type GExiv2StreamCanWrite = extern "C" fn(handle: *mut c_void) -> c_int;
+/// Callback returning the total length of the stream in bytes.
+pub type GExiv2StreamLength = extern "C" fn(handle: *mut c_void) -> i64;
+/// Callback returning the current position within the stream.
+pub type GExiv2StreamPosition = extern "C" fn(handle: *mut c_void) -> i64;
+/// Callback that moves the current position of the stream.
+pub type GExiv2StreamSeek = extern "C" fn(handle: *mut c_void, offset: i64, origin: WrapperSeekOrigin);
+/// Callback that reads up to `count` bytes into `buffer`, starting at `buffer + offset`, and
+/// returns the number of bytes read.
+pub type GExiv2StreamRead =
+    extern "C" fn(handle: *mut c_void, buffer: *mut c_void, offset: i32, count: i32) -> i32;
+/// Callback that writes `count` bytes from `buffer`, starting at `buffer + offset`.
+pub type GExiv2StreamWrite =
+    extern "C" fn(handle: *mut c_void, buffer: *mut c_void, offset: i32, count: i32);
+/// Callback that flushes any buffered writes to the underlying storage.
+pub type GExiv2StreamFlush = extern "C" fn(handle: *mut c_void);
+
+/// Set of callbacks through which gexiv2 accesses a caller-managed stream.
+///
+/// The `handle` is passed back verbatim as the first argument of every callback. See the
+/// [`stream`](stream/index.html) module for an adapter that builds one from a Rust stream.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct GExiv2ManagedStreamCallbacks {
+    pub handle: *mut c_void,
+    pub can_seek: GExiv2StreamCanSeek,
+    pub can_read: GExiv2StreamCanRead,
+    pub can_write: GExiv2StreamCanWrite,
+    pub length: GExiv2StreamLength,
+    pub position: GExiv2StreamPosition,
+    pub read: GExiv2StreamRead,
+    pub write: GExiv2StreamWrite,
+    pub seek: GExiv2StreamSeek,
+    pub flush: GExiv2StreamFlush,
+}
+
 extern "C" {
     pub fn gexiv2_get_version() -> c_int;
     pub fn gexiv2_initialize() -> c_int;
 
+    // GObject type registration.
+    pub fn gexiv2_metadata_get_type() -> GType;
+    pub fn gexiv2_preview_properties_get_type() -> GType;
+    pub fn gexiv2_preview_image_get_type() -> GType;
+
     // GExiv2Metadata lifecycle management.
     pub fn gexiv2_metadata_new() -> *mut GExiv2Metadata;
     pub fn gexiv2_metadata_free(this: *mut GExiv2Metadata);
     pub fn gexiv2_metadata_open_path(this: *mut GExiv2Metadata, path: *const c_char, error: *mut *mut GError) -> c_int;
     pub fn gexiv2_metadata_open_buf(Gthis: *mut GExiv2Metadata, data: *const u8, data_len: c_long, error: *mut *mut GError) -> c_int;
-    pub fn gexiv2_metadata_from_app1_segment(Gthis: *mut GExiv2Metadata, data: *const u8, data_len: c_long, error: *mut *mut GError) -> c_int;
     pub fn gexiv2_metadata_save_file(this: *mut GExiv2Metadata, path: *const c_char, error: *mut *mut GError) -> c_int;
+    pub fn gexiv2_metadata_open_stream(
+        this: *mut GExiv2Metadata,
+        cb: *mut GExiv2ManagedStreamCallbacks,
+        error: *mut *mut GError,
+    ) -> c_int;
+    pub fn gexiv2_metadata_save_stream(
+        this: *mut GExiv2Metadata,
+        cb: *mut GExiv2ManagedStreamCallbacks,
+        error: *mut *mut GError,
+    ) -> c_int;
 
     // Image information.
     pub fn gexiv2_metadata_get_supports_exif(this: *mut GExiv2Metadata) -> c_int;
     pub fn gexiv2_metadata_get_supports_iptc(this: *mut GExiv2Metadata) -> c_int;
     pub fn gexiv2_metadata_get_supports_xmp(this: *mut GExiv2Metadata) -> c_int;
+    /// The returned string is owned by `this` and must not be freed.
     pub fn gexiv2_metadata_get_mime_type(this: *mut GExiv2Metadata) -> *const c_char;
     pub fn gexiv2_metadata_get_pixel_width(this: *mut GExiv2Metadata) -> c_int;
     pub fn gexiv2_metadata_get_pixel_height(this: *mut GExiv2Metadata) -> c_int;
 
     // Tag management.
-    pub fn gexiv2_metadata_has_tag(this: *mut GExiv2Metadata, tag: *const c_char) -> c_int;
-    pub fn gexiv2_metadata_clear_tag(this: *mut GExiv2Metadata, tag: *const c_char) -> c_int;
     pub fn gexiv2_metadata_clear(this: *mut GExiv2Metadata);
     pub fn gexiv2_metadata_has_exif(this: *mut GExiv2Metadata) -> c_int;
     pub fn gexiv2_metadata_clear_exif(this: *mut GExiv2Metadata);
+    /// The returned array is owned by the caller; free it with [`g_strfreev()`](fn.g_strfreev.html).
     pub fn gexiv2_metadata_get_exif_tags(this: *mut GExiv2Metadata) -> *mut *mut c_char;
     pub fn gexiv2_metadata_has_xmp(this: *mut GExiv2Metadata) -> c_int;
     pub fn gexiv2_metadata_clear_xmp(this: *mut GExiv2Metadata);
+    /// The returned array is owned by the caller; free it with [`g_strfreev()`](fn.g_strfreev.html).
     pub fn gexiv2_metadata_get_xmp_tags(this: *mut GExiv2Metadata) -> *mut *mut c_char;
     pub fn gexiv2_metadata_has_iptc(this: *mut GExiv2Metadata) -> c_int;
     pub fn gexiv2_metadata_clear_iptc(this: *mut GExiv2Metadata);
+    /// The returned array is owned by the caller; free it with [`g_strfreev()`](fn.g_strfreev.html).
     pub fn gexiv2_metadata_get_iptc_tags(this: *mut GExiv2Metadata) -> *mut *mut c_char;
 
+    // Tag information functions.
+    pub fn gexiv2_metadata_is_exif_tag(tag: *const c_char) -> c_int;
+    pub fn gexiv2_metadata_is_iptc_tag(tag: *const c_char) -> c_int;
+    pub fn gexiv2_metadata_is_xmp_tag(tag: *const c_char) -> c_int;
+
+    // Exif thumbnail getter/setters.
+    /// On success, `buffer` points to data owned by the caller; free it with
+    /// [`g_free()`](fn.g_free.html).
+    pub fn gexiv2_metadata_get_exif_thumbnail(
+        this: *mut GExiv2Metadata,
+        buffer: *mut *mut u8,
+        size: *mut c_int,
+        error: *mut *mut GError,
+    ) -> c_int;
+    pub fn gexiv2_metadata_set_exif_thumbnail_from_file(
+        this: *mut GExiv2Metadata,
+        path: *const c_char,
+        error: *mut *mut GError,
+    ) -> c_int;
+
+    // Preview image properties.
+    /// The returned array and its elements are owned by `this` and must not be freed.
+    pub fn gexiv2_metadata_get_preview_properties(
+        this: *mut GExiv2Metadata,
+    ) -> *mut *mut GExiv2PreviewProperties;
+    /// The returned string is owned by `this` and must not be freed.
+    pub fn gexiv2_preview_properties_get_mime_type(
+        this: *mut GExiv2PreviewProperties,
+    ) -> *const c_char;
+    /// The returned string is owned by `this` and must not be freed.
+    pub fn gexiv2_preview_properties_get_extension(
+        this: *mut GExiv2PreviewProperties,
+    ) -> *const c_char;
+    pub fn gexiv2_preview_properties_get_size(this: *mut GExiv2PreviewProperties) -> c_uint;
+    pub fn gexiv2_preview_properties_get_width(this: *mut GExiv2PreviewProperties) -> c_uint;
+    pub fn gexiv2_preview_properties_get_height(this: *mut GExiv2PreviewProperties) -> c_uint;
+
+    // Preview images.
+    pub fn gexiv2_preview_image_free(this: *mut GExiv2PreviewImage);
+    /// The returned data is owned by `this` and must not be freed.
+    pub fn gexiv2_preview_image_get_data(
+        this: *mut GExiv2PreviewImage,
+        size: *mut c_uint,
+    ) -> *const c_uchar;
+    /// The returned string is owned by `this` and must not be freed.
+    pub fn gexiv2_preview_image_get_mime_type(this: *mut GExiv2PreviewImage) -> *const c_char;
+    /// The returned string is owned by `this` and must not be freed.
+    pub fn gexiv2_preview_image_get_extension(this: *mut GExiv2PreviewImage) -> *const c_char;
+    pub fn gexiv2_preview_image_get_width(this: *mut GExiv2PreviewImage) -> c_uint;
+    pub fn gexiv2_preview_image_get_height(this: *mut GExiv2PreviewImage) -> c_uint;
+
+    // Logging.
+    pub fn gexiv2_log_get_default_handler() -> GExiv2LogHandler;
+    pub fn gexiv2_log_get_handler() -> GExiv2LogHandler;
+    pub fn gexiv2_log_set_handler(handler: GExiv2LogHandler);
+    pub fn gexiv2_log_get_level() -> GExiv2LogLevel;
+    pub fn gexiv2_log_set_level(level: GExiv2LogLevel);
+    pub fn gexiv2_log_use_glib_logging();
+}
+
+// Functions deprecated in favour of their `try_` variants, and removed in gexiv2 0.16.
+#[cfg(not(gexiv2_0_16))]
+extern "C" {
+    // Tag management.
+    pub fn gexiv2_metadata_has_tag(this: *mut GExiv2Metadata, tag: *const c_char) -> c_int;
+    pub fn gexiv2_metadata_clear_tag(this: *mut GExiv2Metadata, tag: *const c_char) -> c_int;
+
     // Tag data getters/setters.
+    /// The returned string is owned by the caller; free it with [`g_free()`](fn.g_free.html).
     pub fn gexiv2_metadata_get_tag_string(
         this: *mut GExiv2Metadata,
         tag: *const c_char,
@@ -138,10 +377,12 @@ extern "C" {
         value: *const c_char,
         error: *mut *mut GError,
     ) -> c_int;
+    /// The returned string is owned by the caller; free it with [`g_free()`](fn.g_free.html).
     pub fn gexiv2_metadata_get_tag_interpreted_string(
         this: *mut GExiv2Metadata,
         tag: *const c_char,
     ) -> *const c_char;
+    /// The returned array is owned by the caller; free it with [`g_strfreev()`](fn.g_strfreev.html).
     pub fn gexiv2_metadata_get_tag_multiple(
         this: *mut GExiv2Metadata,
         tag: *const c_char,
@@ -153,6 +394,11 @@ extern "C" {
         values: *mut *const c_char,
         error: *mut *mut GError,
     ) -> c_int;
+    pub fn gexiv2_metadata_set_xmp_tag_struct(
+        this: *mut GExiv2Metadata,
+        tag: *const c_char,
+        type_: GExiv2StructureType,
+    ) -> c_int;
     pub fn gexiv2_metadata_get_tag_long(
         this: *mut GExiv2Metadata,
         tag: *const c_char,
@@ -209,6 +455,7 @@ extern "C" {
     pub fn gexiv2_metadata_get_fnumber(this: *mut GExiv2Metadata, error: *mut *mut GError) -> c_double;
     pub fn gexiv2_metadata_get_focal_length(this: *mut GExiv2Metadata, error: *mut *mut GError) -> c_double;
     pub fn gexiv2_metadata_get_iso_speed(this: *mut GExiv2Metadata, error: *mut *mut GError) -> c_int;
+    /// The returned string is owned by the caller; free it with [`g_free()`](fn.g_free.html).
     pub fn gexiv2_metadata_get_comment(this: *mut GExiv2Metadata, error: *mut *mut GError) -> *const c_char;
     pub fn gexiv2_metadata_set_comment(this: *mut GExiv2Metadata, comment: *const c_char, error: *mut *mut GError);
     pub fn gexiv2_metadata_clear_comment(this: *mut GExiv2Metadata, error: *mut *mut GError);
@@ -246,34 +493,23 @@ extern "C" {
     pub fn gexiv2_metadata_delete_gps_info(this: *mut GExiv2Metadata);
 
     // Tag information functions.
-    pub fn gexiv2_metadata_is_exif_tag(tag: *const c_char) -> c_int;
-    pub fn gexiv2_metadata_is_iptc_tag(tag: *const c_char) -> c_int;
-    pub fn gexiv2_metadata_is_xmp_tag(tag: *const c_char) -> c_int;
+    /// The returned string is interned by GLib and must not be freed.
     pub fn gexiv2_metadata_get_tag_label(
         tag: *const c_char,
         error: *mut *mut GError,
     ) -> *const c_char;
+    /// The returned string is interned by GLib and must not be freed.
     pub fn gexiv2_metadata_get_tag_description(
         tag: *const c_char,
         error: *mut *mut GError,
     ) -> *const c_char;
+    /// The returned string is interned by GLib and must not be freed.
     pub fn gexiv2_metadata_get_tag_type(
         tag: *const c_char,
         error: *mut *mut GError,
     ) -> *const c_char;
 
     // Exif thumbnail getter/setters.
-    pub fn gexiv2_metadata_get_exif_thumbnail(
-        this: *mut GExiv2Metadata,
-        buffer: *mut *mut u8,
-        size: *mut c_int,
-        error: *mut *mut GError,
-    ) -> c_int;
-    pub fn gexiv2_metadata_set_exif_thumbnail_from_file(
-        this: *mut GExiv2Metadata,
-        path: *const c_char,
-        error: *mut *mut GError,
-    ) -> c_int;
     pub fn gexiv2_metadata_set_exif_thumbnail_from_buffer(
         this: *mut GExiv2Metadata,
         buffer: *const u8,
@@ -282,34 +518,13 @@ extern "C" {
     );
     pub fn gexiv2_metadata_erase_exif_thumbnail(this: *mut GExiv2Metadata);
 
-    // Preview image properties.
-    pub fn gexiv2_metadata_get_preview_properties(
-        this: *mut GExiv2Metadata,
-    ) -> *mut *mut GExiv2PreviewProperties;
-    pub fn gexiv2_preview_properties_get_mime_type(
-        this: *mut GExiv2PreviewProperties,
-    ) -> *const c_char;
-    pub fn gexiv2_preview_properties_get_extension(
-        this: *mut GExiv2PreviewProperties,
-    ) -> *const c_char;
-    pub fn gexiv2_preview_properties_get_size(this: *mut GExiv2PreviewProperties) -> c_uint;
-    pub fn gexiv2_preview_properties_get_width(this: *mut GExiv2PreviewProperties) -> c_uint;
-    pub fn gexiv2_preview_properties_get_height(this: *mut GExiv2PreviewProperties) -> c_uint;
-
     // Preview images.
+    /// The returned image is owned by the caller; free it with
+    /// [`gexiv2_preview_image_free()`](fn.gexiv2_preview_image_free.html).
     pub fn gexiv2_metadata_get_preview_image(
         this: *mut GExiv2Metadata,
         props: *mut GExiv2PreviewProperties,
     ) -> *mut GExiv2PreviewImage;
-    pub fn gexiv2_preview_image_free(this: *mut GExiv2PreviewImage);
-    pub fn gexiv2_preview_image_get_data(
-        this: *mut GExiv2PreviewImage,
-        size: *mut c_uint,
-    ) -> *const c_uchar;
-    pub fn gexiv2_preview_image_get_mime_type(this: *mut GExiv2PreviewImage) -> *const c_char;
-    pub fn gexiv2_preview_image_get_extension(this: *mut GExiv2PreviewImage) -> *const c_char;
-    pub fn gexiv2_preview_image_get_width(this: *mut GExiv2PreviewImage) -> c_uint;
-    pub fn gexiv2_preview_image_get_height(this: *mut GExiv2PreviewImage) -> c_uint;
     pub fn gexiv2_preview_image_write_file(
         this: *mut GExiv2PreviewImage,
         path: *const c_char,
@@ -322,27 +537,294 @@ extern "C" {
     ) -> c_int;
     pub fn gexiv2_metadata_unregister_xmp_namespace(name: *const c_char) -> c_int;
     pub fn gexiv2_metadata_unregister_all_xmp_namespaces();
+}
 
-    // Logging.
-    pub fn gexiv2_log_get_default_handler() -> GExiv2LogHandler;
-    pub fn gexiv2_log_get_handler() -> GExiv2LogHandler;
-    pub fn gexiv2_log_set_handler(handler: GExiv2LogHandler);
-    pub fn gexiv2_log_get_level() -> GExiv2LogLevel;
-    pub fn gexiv2_log_set_level(level: GExiv2LogLevel);
-    pub fn gexiv2_log_use_glib_logging();
+#[cfg(all(gexiv2_0_12, not(gexiv2_0_16)))]
+extern "C" {
+    /// Look up the namespace URI for an XMP tag (e.g. `Xmp.dc.title`) or bare prefix (`dc`).
+    ///
+    /// The returned string is owned by the caller; free it with [`g_free()`](fn.g_free.html).
+    pub fn gexiv2_metadata_get_xmp_namespace_for_tag(tag: *const c_char) -> *mut c_char;
+}
+
+// GLib functions for dealing with values gexiv2 hands back to the caller.
+extern "C" {
+    pub fn g_free(mem: *mut c_void);
+    pub fn g_strfreev(str_array: *mut *mut c_char);
+    pub fn g_error_free(error: *mut GError);
+    pub fn g_quark_to_string(quark: u32) -> *const c_char;
+}
+
+// Lifecycle functions added in gexiv2 0.12.
+#[cfg(gexiv2_0_12)]
+extern "C" {
+    pub fn gexiv2_metadata_from_app1_segment(Gthis: *mut GExiv2Metadata, data: *const u8, data_len: c_long, error: *mut *mut GError) -> c_int;
+    /// Write the metadata to an XMP sidecar file at `path`, leaving the original media untouched.
+    pub fn gexiv2_metadata_save_external(this: *mut GExiv2Metadata, path: *const c_char, error: *mut *mut GError) -> c_int;
+}
+
+// Variants of the functions above that report failures through a `GError` rather than silently
+// swallowing them. These supersede their counterparts, many of which are deprecated in gexiv2.
+#[cfg(gexiv2_0_14)]
+extern "C" {
+    // Tag management.
+    pub fn gexiv2_metadata_try_has_tag(
+        this: *mut GExiv2Metadata,
+        tag: *const c_char,
+        error: *mut *mut GError,
+    ) -> c_int;
+    pub fn gexiv2_metadata_try_clear_tag(
+        this: *mut GExiv2Metadata,
+        tag: *const c_char,
+        error: *mut *mut GError,
+    ) -> c_int;
+    /// Whether the tag can hold multiple values, i.e. should be written with
+    /// [`gexiv2_metadata_try_set_tag_multiple()`](fn.gexiv2_metadata_try_set_tag_multiple.html).
+    pub fn gexiv2_metadata_try_tag_supports_multiple_values(
+        this: *mut GExiv2Metadata,
+        tag: *const c_char,
+        error: *mut *mut GError,
+    ) -> c_int;
+
+    // Tag data getters/setters.
+    /// The returned string is owned by the caller; free it with [`g_free()`](fn.g_free.html).
+    pub fn gexiv2_metadata_try_get_tag_string(
+        this: *mut GExiv2Metadata,
+        tag: *const c_char,
+        error: *mut *mut GError,
+    ) -> *const c_char;
+    pub fn gexiv2_metadata_try_set_tag_string(
+        this: *mut GExiv2Metadata,
+        tag: *const c_char,
+        value: *const c_char,
+        error: *mut *mut GError,
+    ) -> c_int;
+    /// The returned string is owned by the caller; free it with [`g_free()`](fn.g_free.html).
+    pub fn gexiv2_metadata_try_get_tag_interpreted_string(
+        this: *mut GExiv2Metadata,
+        tag: *const c_char,
+        error: *mut *mut GError,
+    ) -> *const c_char;
+    /// The returned array is owned by the caller; free it with [`g_strfreev()`](fn.g_strfreev.html).
+    pub fn gexiv2_metadata_try_get_tag_multiple(
+        this: *mut GExiv2Metadata,
+        tag: *const c_char,
+        error: *mut *mut GError,
+    ) -> *mut *mut c_char;
+    pub fn gexiv2_metadata_try_set_tag_multiple(
+        this: *mut GExiv2Metadata,
+        tag: *const c_char,
+        values: *mut *const c_char,
+        error: *mut *mut GError,
+    ) -> c_int;
+    pub fn gexiv2_metadata_try_set_xmp_tag_struct(
+        this: *mut GExiv2Metadata,
+        tag: *const c_char,
+        type_: GExiv2StructureType,
+        error: *mut *mut GError,
+    ) -> c_int;
+    pub fn gexiv2_metadata_try_get_tag_long(
+        this: *mut GExiv2Metadata,
+        tag: *const c_char,
+        error: *mut *mut GError,
+    ) -> c_long;
+    pub fn gexiv2_metadata_try_set_tag_long(
+        this: *mut GExiv2Metadata,
+        tag: *const c_char,
+        value: c_long,
+        error: *mut *mut GError,
+    ) -> c_int;
+    pub fn gexiv2_metadata_try_get_exif_tag_rational(
+        this: *mut GExiv2Metadata,
+        tag: *const c_char,
+        nom: *mut c_int,
+        den: *mut c_int,
+        error: *mut *mut GError,
+    ) -> c_int;
+    pub fn gexiv2_metadata_try_set_exif_tag_rational(
+        this: *mut GExiv2Metadata,
+        tag: *const c_char,
+        nom: c_int,
+        den: c_int,
+        error: *mut *mut GError,
+    ) -> c_int;
+
+    // Helper & convenience getters/setters.
+    pub fn gexiv2_metadata_try_get_orientation(
+        this: *mut GExiv2Metadata,
+        error: *mut *mut GError,
+    ) -> Orientation;
+    pub fn gexiv2_metadata_try_set_orientation(
+        this: *mut GExiv2Metadata,
+        orientation: Orientation,
+        error: *mut *mut GError,
+    );
+    pub fn gexiv2_metadata_try_get_metadata_pixel_width(
+        this: *mut GExiv2Metadata,
+        error: *mut *mut GError,
+    ) -> c_int;
+    pub fn gexiv2_metadata_try_get_metadata_pixel_height(
+        this: *mut GExiv2Metadata,
+        error: *mut *mut GError,
+    ) -> c_int;
+    pub fn gexiv2_metadata_try_set_metadata_pixel_width(
+        this: *mut GExiv2Metadata,
+        width: c_int,
+        error: *mut *mut GError,
+    );
+    pub fn gexiv2_metadata_try_set_metadata_pixel_height(
+        this: *mut GExiv2Metadata,
+        height: c_int,
+        error: *mut *mut GError,
+    );
+    pub fn gexiv2_metadata_try_get_exposure_time(
+        this: *mut GExiv2Metadata,
+        nom: *mut c_int,
+        den: *mut c_int,
+        error: *mut *mut GError,
+    ) -> c_int;
+    pub fn gexiv2_metadata_try_get_fnumber(this: *mut GExiv2Metadata, error: *mut *mut GError) -> c_double;
+    pub fn gexiv2_metadata_try_get_focal_length(this: *mut GExiv2Metadata, error: *mut *mut GError) -> c_double;
+    pub fn gexiv2_metadata_try_get_iso_speed(this: *mut GExiv2Metadata, error: *mut *mut GError) -> c_int;
+    /// The returned string is owned by the caller; free it with [`g_free()`](fn.g_free.html).
+    pub fn gexiv2_metadata_try_get_comment(this: *mut GExiv2Metadata, error: *mut *mut GError) -> *const c_char;
+    pub fn gexiv2_metadata_try_set_comment(this: *mut GExiv2Metadata, comment: *const c_char, error: *mut *mut GError);
+    pub fn gexiv2_metadata_try_clear_comment(this: *mut GExiv2Metadata, error: *mut *mut GError);
+
+    // GPS-related functions.
+    pub fn gexiv2_metadata_try_get_gps_longitude(
+        this: *mut GExiv2Metadata,
+        longitude: *mut c_double,
+        error: *mut *mut GError,
+    ) -> c_int;
+    pub fn gexiv2_metadata_try_get_gps_latitude(
+        this: *mut GExiv2Metadata,
+        latitude: *mut c_double,
+        error: *mut *mut GError,
+    ) -> c_int;
+    pub fn gexiv2_metadata_try_get_gps_altitude(
+        this: *mut GExiv2Metadata,
+        altitude: *mut c_double,
+        error: *mut *mut GError,
+    ) -> c_int;
+    pub fn gexiv2_metadata_try_get_gps_info(
+        this: *mut GExiv2Metadata,
+        longitude: *mut c_double,
+        latitude: *mut c_double,
+        altitude: *mut c_double,
+        error: *mut *mut GError,
+    ) -> c_int;
+    pub fn gexiv2_metadata_try_set_gps_info(
+        this: *mut GExiv2Metadata,
+        longitude: c_double,
+        latitude: c_double,
+        altitude: c_double,
+        error: *mut *mut GError,
+    ) -> c_int;
+    pub fn gexiv2_metadata_try_update_gps_info(
+        this: *mut GExiv2Metadata,
+        longitude: c_double,
+        latitude: c_double,
+        altitude: c_double,
+        error: *mut *mut GError,
+    ) -> c_int;
+    pub fn gexiv2_metadata_try_delete_gps_info(this: *mut GExiv2Metadata, error: *mut *mut GError);
+
+    // Tag information functions.
+    /// The returned string is interned by GLib and must not be freed.
+    pub fn gexiv2_metadata_try_get_tag_label(
+        tag: *const c_char,
+        error: *mut *mut GError,
+    ) -> *const c_char;
+    /// The returned string is interned by GLib and must not be freed.
+    pub fn gexiv2_metadata_try_get_tag_description(
+        tag: *const c_char,
+        error: *mut *mut GError,
+    ) -> *const c_char;
+    /// The returned string is interned by GLib and must not be freed.
+    pub fn gexiv2_metadata_try_get_tag_type(
+        tag: *const c_char,
+        error: *mut *mut GError,
+    ) -> *const c_char;
+
+    // Exif thumbnail getter/setters.
+    pub fn gexiv2_metadata_try_set_exif_thumbnail_from_buffer(
+        this: *mut GExiv2Metadata,
+        buffer: *const u8,
+        size: c_int,
+        error: *mut *mut GError,
+    );
+    pub fn gexiv2_metadata_try_erase_exif_thumbnail(this: *mut GExiv2Metadata, error: *mut *mut GError);
+
+    // Preview images.
+    /// The returned image is owned by the caller; free it with
+    /// [`gexiv2_preview_image_free()`](fn.gexiv2_preview_image_free.html).
+    pub fn gexiv2_metadata_try_get_preview_image(
+        this: *mut GExiv2Metadata,
+        props: *mut GExiv2PreviewProperties,
+        error: *mut *mut GError,
+    ) -> *mut GExiv2PreviewImage;
+    pub fn gexiv2_preview_image_try_write_file(
+        this: *mut GExiv2PreviewImage,
+        path: *const c_char,
+        error: *mut *mut GError,
+    ) -> c_long;
+
+    // XMP namespace management.
+    pub fn gexiv2_metadata_try_register_xmp_namespace(
+        name: *const c_char,
+        prefix: *const c_char,
+        error: *mut *mut GError,
+    ) -> c_int;
+    pub fn gexiv2_metadata_try_unregister_xmp_namespace(
+        name: *const c_char,
+        error: *mut *mut GError,
+    ) -> c_int;
+    pub fn gexiv2_metadata_try_unregister_all_xmp_namespaces(error: *mut *mut GError);
+    /// Look up the namespace URI for an XMP tag (e.g. `Xmp.dc.title`) or bare prefix (`dc`).
+    ///
+    /// The returned string is owned by the caller; free it with [`g_free()`](fn.g_free.html).
+    pub fn gexiv2_metadata_try_get_xmp_namespace_for_tag(
+        tag: *const c_char,
+        error: *mut *mut GError,
+    ) -> *mut c_char;
 }
 
-#[cfg(feature = "raw-tag-access")]
+#[cfg(any(feature = "raw-tag-access", feature = "exif-data-access"))]
 extern crate glib_sys as glib;
 
-#[cfg(feature = "raw-tag-access")]
+#[cfg(all(feature = "raw-tag-access", not(gexiv2_0_16)))]
 extern "C" {
+    /// The returned `GBytes` is owned by the caller; release it with `g_bytes_unref()`.
     pub fn gexiv2_metadata_get_tag_raw(
         this: *mut GExiv2Metadata,
         tag: *const libc::c_char,
     ) -> *mut glib::GBytes;
 }
 
+#[cfg(all(feature = "raw-tag-access", gexiv2_0_14))]
+extern "C" {
+    /// The returned `GBytes` is owned by the caller; release it with `g_bytes_unref()`.
+    pub fn gexiv2_metadata_try_get_tag_raw(
+        this: *mut GExiv2Metadata,
+        tag: *const libc::c_char,
+        error: *mut *mut GError,
+    ) -> *mut glib::GBytes;
+}
+
+#[cfg(all(feature = "exif-data-access", gexiv2_0_14))]
+extern "C" {
+    /// Serialize the Exif data as a TIFF structure, i.e. an APP1 payload without the leading
+    /// `Exif\0\0` identifier, in the given byte order. Returns null if there is no Exif data.
+    ///
+    /// The returned `GBytes` is owned by the caller; release it with `g_bytes_unref()`.
+    pub fn gexiv2_metadata_get_exif_data(
+        this: *mut GExiv2Metadata,
+        byte_order: GExiv2ByteOrder,
+        error: *mut *mut GError,
+    ) -> *mut glib::GBytes;
+}
+
 #[cfg(feature = "xmp-packet-access")]
 #[macro_use]
 extern crate bitflags;
@@ -360,15 +842,55 @@ bitflags! {
     }
 }
 
-#[cfg(feature = "xmp-packet-access")]
+#[cfg(all(feature = "xmp-packet-access", not(gexiv2_0_16)))]
 extern "C" {
+    /// The returned string is owned by the caller; free it with [`g_free()`](fn.g_free.html).
     pub fn gexiv2_metadata_generate_xmp_packet(
         this: *mut GExiv2Metadata,
-        xmp_format_flags: libc::c_ulong,
+        xmp_format_flags: libc::c_uint,
         padding: u32,
     ) -> *const c_char;
+    /// The returned string is owned by the caller; free it with [`g_free()`](fn.g_free.html).
     pub fn gexiv2_metadata_get_xmp_packet(this: *mut GExiv2Metadata) -> *const c_char;
 }
 
-#[cfg(test)]
+#[cfg(all(feature = "xmp-packet-access", gexiv2_0_14))]
+extern "C" {
+    /// The returned string is owned by the caller; free it with [`g_free()`](fn.g_free.html).
+    pub fn gexiv2_metadata_try_generate_xmp_packet(
+        this: *mut GExiv2Metadata,
+        xmp_format_flags: libc::c_uint,
+        padding: u32,
+        error: *mut *mut GError,
+    ) -> *const c_char;
+    /// The returned string is owned by the caller; free it with [`g_free()`](fn.g_free.html).
+    pub fn gexiv2_metadata_try_get_xmp_packet(
+        this: *mut GExiv2Metadata,
+        error: *mut *mut GError,
+    ) -> *const c_char;
+}
+
+/// Call [`gexiv2_initialize()`](fn.gexiv2_initialize.html) exactly once per process, however many
+/// threads call this, and return whether it succeeded.
+///
+/// gexiv2 must be initialized before it is used from more than one thread, since that sets up
+/// Exiv2's XMP toolkit, which is not safe to do concurrently. Every thread that might be the
+/// first to use gexiv2 can call this; calls after the first return immediately. The
+/// constructors in the `safe` module call it themselves.
+#[cfg(not(feature = "dlopen"))]
+pub fn ensure_initialized() -> bool {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+    static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+    INIT.call_once(|| INITIALIZED.store(unsafe { gexiv2_initialize() } == 1, Ordering::Release));
+    INITIALIZED.load(Ordering::Acquire)
+}
+
+// The tests of the crate-root functions need gexiv2 linked, which it is not with `dlopen`.
+#[cfg(all(test, not(feature = "dlopen")))]
 mod test;
+#[cfg(all(test, feature = "dlopen"))]
+mod test_dlopen;
@@ -0,0 +1,177 @@
+// Copyright © 2015–2022 Felix A. Crux <felixc@felixcrux.com> and contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Routing gexiv2's log messages to Rust closures.
+//!
+//! A [`GExiv2LogHandler`](../type.GExiv2LogHandler.html) is a bare function
+//! pointer, with no user-data pointer to carry any state. This module keeps a
+//! single global closure instead, and installs a handler that passes every
+//! message on to it:
+//!
+//! ```no_run
+//! # extern crate gexiv2_sys as gexiv2;
+//! gexiv2::logging::set_handler(|level, msg| eprintln!("gexiv2 {:?}: {}", level, msg));
+//! ```
+//!
+//! With the `log` or `tracing` feature, `install_log_handler()` or
+//! `install_tracing_handler()` set up a closure that emits the messages as
+//! records or events with the target `gexiv2`, and set gexiv2's own log level
+//! to match the most verbose level enabled on the Rust side, so messages that
+//! would be discarded are not even formatted.
+//!
+//! The handler and level are global to the process, so the functions here
+//! change them while holding [`threads::global_lock()`](../threads/fn.global_lock.html).
+//!
+//! Handlers are called without any lock held by this module, so they may
+//! re-enter it, e.g. to replace themselves with `set_handler()`; the replaced
+//! handler still gets the message it was called with. The exception is a
+//! message logged by a call made while holding the global lock, like
+//! [`threads::register_xmp_namespace()`](../threads/fn.register_xmp_namespace.html):
+//! the lock is not reentrant, so the handler must then not call anything that
+//! takes it.
+
+use std::ffi::CStr;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, PoisonError, RwLock};
+
+use super::libc::c_char;
+use super::*;
+
+/// Target that messages are logged with by the `log` and `tracing` handlers.
+pub const TARGET: &str = "gexiv2";
+
+type Handler = Arc<dyn Fn(GExiv2LogLevel, &str) + Send + Sync>;
+
+/// The closure `dispatch()` passes messages on to, cloned out so it runs without the lock held.
+static HANDLER: RwLock<Option<Handler>> = RwLock::new(None);
+
+/// Pass gexiv2's log messages on to `handler`, replacing any handler installed before.
+///
+/// The message is passed without its trailing newline. A panic in `handler` is
+/// caught and the message dropped, since it cannot unwind into gexiv2.
+pub fn set_handler<F: Fn(GExiv2LogLevel, &str) + Send + Sync + 'static>(handler: F) {
+    let _lock = threads::global_lock();
+    *HANDLER.write().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(handler));
+    unsafe { gexiv2_log_set_handler(dispatch) }
+}
+
+/// Restore gexiv2's default handler, and drop the closure installed by `set_handler()`.
+pub fn reset_handler() {
+    let _lock = threads::global_lock();
+    unsafe { gexiv2_log_set_handler(gexiv2_log_get_default_handler()) }
+    *HANDLER.write().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+extern "C" fn dispatch(level: GExiv2LogLevel, msg: *const c_char) {
+    if msg.is_null() {
+        return;
+    }
+    let msg = unsafe { CStr::from_ptr(msg) }.to_string_lossy();
+    let handler = HANDLER
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    if let Some(handler) = handler {
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| handler(level, msg.trim_end())));
+    }
+}
+
+/// The `log` level corresponding to a gexiv2 one, or `None` for `MUTE`.
+#[cfg(feature = "log")]
+pub fn log_level(level: GExiv2LogLevel) -> Option<log::Level> {
+    match level {
+        GExiv2LogLevel::DEBUG => Some(log::Level::Debug),
+        GExiv2LogLevel::INFO => Some(log::Level::Info),
+        GExiv2LogLevel::WARN => Some(log::Level::Warn),
+        GExiv2LogLevel::ERROR => Some(log::Level::Error),
+        GExiv2LogLevel::MUTE => None,
+    }
+}
+
+/// Set gexiv2's log level to the most verbose one `log::max_level()` lets through.
+#[cfg(feature = "log")]
+pub fn sync_log_level() {
+    let level = match log::max_level() {
+        log::LevelFilter::Off => GExiv2LogLevel::MUTE,
+        log::LevelFilter::Error => GExiv2LogLevel::ERROR,
+        log::LevelFilter::Warn => GExiv2LogLevel::WARN,
+        log::LevelFilter::Info => GExiv2LogLevel::INFO,
+        log::LevelFilter::Debug | log::LevelFilter::Trace => GExiv2LogLevel::DEBUG,
+    };
+    let _lock = threads::global_lock();
+    unsafe { gexiv2_log_set_level(level) }
+}
+
+/// Emit gexiv2's log messages as `log` records, and sync the log level with `log::max_level()`.
+///
+/// Call `sync_log_level()` again if the maximum level changes later on.
+#[cfg(feature = "log")]
+pub fn install_log_handler() {
+    sync_log_level();
+    set_handler(|level, msg| {
+        if let Some(level) = log_level(level) {
+            log::log!(target: TARGET, level, "{}", msg);
+        }
+    });
+}
+
+/// The `tracing` level corresponding to a gexiv2 one, or `None` for `MUTE`.
+#[cfg(feature = "tracing")]
+pub fn tracing_level(level: GExiv2LogLevel) -> Option<tracing::Level> {
+    match level {
+        GExiv2LogLevel::DEBUG => Some(tracing::Level::DEBUG),
+        GExiv2LogLevel::INFO => Some(tracing::Level::INFO),
+        GExiv2LogLevel::WARN => Some(tracing::Level::WARN),
+        GExiv2LogLevel::ERROR => Some(tracing::Level::ERROR),
+        GExiv2LogLevel::MUTE => None,
+    }
+}
+
+/// Set gexiv2's log level to the most verbose one any current `tracing` subscriber is interested in.
+#[cfg(feature = "tracing")]
+pub fn sync_tracing_level() {
+    use tracing::level_filters::LevelFilter;
+
+    let filter = LevelFilter::current();
+    let level = if filter >= LevelFilter::DEBUG {
+        GExiv2LogLevel::DEBUG
+    } else if filter >= LevelFilter::INFO {
+        GExiv2LogLevel::INFO
+    } else if filter >= LevelFilter::WARN {
+        GExiv2LogLevel::WARN
+    } else if filter >= LevelFilter::ERROR {
+        GExiv2LogLevel::ERROR
+    } else {
+        GExiv2LogLevel::MUTE
+    };
+    let _lock = threads::global_lock();
+    unsafe { gexiv2_log_set_level(level) }
+}
+
+/// Emit gexiv2's log messages as `tracing` events, and sync the log level with the subscribers'.
+///
+/// Call this after setting up the subscriber, and `sync_tracing_level()` again
+/// if its level changes later on.
+#[cfg(feature = "tracing")]
+pub fn install_tracing_handler() {
+    sync_tracing_level();
+    set_handler(|level, msg| match tracing_level(level) {
+        Some(tracing::Level::DEBUG) => tracing::debug!(target: TARGET, "{}", msg),
+        Some(tracing::Level::INFO) => tracing::info!(target: TARGET, "{}", msg),
+        Some(tracing::Level::WARN) => tracing::warn!(target: TARGET, "{}", msg),
+        Some(_) => tracing::error!(target: TARGET, "{}", msg),
+        None => {}
+    });
+}
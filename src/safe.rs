@@ -0,0 +1,257 @@
+// Copyright © 2015–2022 Felix A. Crux <felixc@felixcrux.com> and contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! A minimal safe wrapper around the raw declarations.
+//!
+//! This covers the boilerplate every consumer of the raw API ends up writing:
+//! owning a [`GExiv2Metadata`](../enum.GExiv2Metadata.html) handle, turning
+//! failures into `Result`s, and converting C strings into Rust ones. For a
+//! complete high-level library, consider the rexiv2 crate instead.
+
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::path::Path;
+
+use super::libc::{c_char, c_int, c_long};
+use super::strings::{take_string, GStrv};
+use super::*;
+
+pub use super::error::Error;
+
+/// Result type returned by fallible operations in this module.
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+/// An owned container for a media file's metadata.
+///
+/// The underlying handle is created when the `Metadata` is and freed when it
+/// is dropped.
+#[derive(Debug)]
+pub struct Metadata {
+    raw: *mut GExiv2Metadata,
+}
+
+// Each handle wraps its own Exiv2 image, and GObject reference counting is atomic, so a
+// `Metadata` can be moved to another thread; not being `Sync` keeps it to one thread at a
+// time. See the `threads` module.
+unsafe impl Send for Metadata {}
+
+impl Metadata {
+    /// Create an empty metadata container; load data into it with one of the `open_*` methods.
+    ///
+    /// Initializes gexiv2 first if that has not happened yet, see
+    /// [`ensure_initialized()`](../fn.ensure_initialized.html).
+    pub fn new() -> Result<Metadata> {
+        ensure_initialized();
+        let raw = unsafe { gexiv2_metadata_new() };
+        if raw.is_null() {
+            return Err(Error::other("gexiv2 could not create a metadata container"));
+        }
+        Ok(Metadata { raw })
+    }
+
+    /// Take ownership of a raw handle, which is freed when the `Metadata` is dropped.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be a valid, non-null handle that the caller owns, and no other pointers to
+    /// it may be used while the `Metadata` exists.
+    pub unsafe fn from_raw(raw: *mut GExiv2Metadata) -> Metadata {
+        Metadata { raw }
+    }
+
+    /// Load the metadata of the media file at the given path.
+    pub fn open_path<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let path = path_to_cstring(path.as_ref())?;
+        check(|err| unsafe { gexiv2_metadata_open_path(self.raw, path.as_ptr(), err) })
+    }
+
+    /// Load the metadata of a media file held in memory.
+    pub fn open_buf(&mut self, data: &[u8]) -> Result<()> {
+        check(|err| unsafe {
+            gexiv2_metadata_open_buf(self.raw, data.as_ptr(), data.len() as c_long, err)
+        })
+    }
+
+    /// Load Exif metadata from the contents of a JPEG APP1 segment.
+    #[cfg(gexiv2_0_12)]
+    pub fn from_app1_segment(&mut self, data: &[u8]) -> Result<()> {
+        check(|err| unsafe {
+            gexiv2_metadata_from_app1_segment(self.raw, data.as_ptr(), data.len() as c_long, err)
+        })
+    }
+
+    /// Write the metadata into the media file at the given path.
+    pub fn save_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path_to_cstring(path.as_ref())?;
+        check(|err| unsafe { gexiv2_metadata_save_file(self.raw, path.as_ptr(), err) })
+    }
+
+    /// Write the metadata into an XMP sidecar file at the given path.
+    #[cfg(gexiv2_0_12)]
+    pub fn save_external<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path_to_cstring(path.as_ref())?;
+        check(|err| unsafe { gexiv2_metadata_save_external(self.raw, path.as_ptr(), err) })
+    }
+
+    /// The MIME type of the loaded media file, if known.
+    pub fn mime_type(&self) -> Option<String> {
+        unsafe { to_string(gexiv2_metadata_get_mime_type(self.raw)) }
+    }
+
+    /// Width of the media in pixels, as determined from the file itself.
+    pub fn pixel_width(&self) -> i32 {
+        unsafe { gexiv2_metadata_get_pixel_width(self.raw) }
+    }
+
+    /// Height of the media in pixels, as determined from the file itself.
+    pub fn pixel_height(&self) -> i32 {
+        unsafe { gexiv2_metadata_get_pixel_height(self.raw) }
+    }
+
+    /// Whether the file format can hold Exif metadata.
+    pub fn supports_exif(&self) -> bool {
+        unsafe { gexiv2_metadata_get_supports_exif(self.raw) == 1 }
+    }
+
+    /// Whether the file format can hold IPTC metadata.
+    pub fn supports_iptc(&self) -> bool {
+        unsafe { gexiv2_metadata_get_supports_iptc(self.raw) == 1 }
+    }
+
+    /// Whether the file format can hold XMP metadata.
+    pub fn supports_xmp(&self) -> bool {
+        unsafe { gexiv2_metadata_get_supports_xmp(self.raw) == 1 }
+    }
+
+    /// Whether any Exif metadata is present.
+    pub fn has_exif(&self) -> bool {
+        unsafe { gexiv2_metadata_has_exif(self.raw) == 1 }
+    }
+
+    /// Whether any IPTC metadata is present.
+    pub fn has_iptc(&self) -> bool {
+        unsafe { gexiv2_metadata_has_iptc(self.raw) == 1 }
+    }
+
+    /// Whether any XMP metadata is present.
+    pub fn has_xmp(&self) -> bool {
+        unsafe { gexiv2_metadata_has_xmp(self.raw) == 1 }
+    }
+
+    /// Names of all Exif tags present.
+    pub fn exif_tags(&self) -> Vec<String> {
+        unsafe { GStrv::from_raw(gexiv2_metadata_get_exif_tags(self.raw)).collect() }
+    }
+
+    /// Names of all IPTC tags present.
+    pub fn iptc_tags(&self) -> Vec<String> {
+        unsafe { GStrv::from_raw(gexiv2_metadata_get_iptc_tags(self.raw)).collect() }
+    }
+
+    /// Names of all XMP tags present.
+    pub fn xmp_tags(&self) -> Vec<String> {
+        unsafe { GStrv::from_raw(gexiv2_metadata_get_xmp_tags(self.raw)).collect() }
+    }
+
+    /// The value of the given tag as a string, or `None` if it is not set.
+    #[cfg(gexiv2_0_14)]
+    pub fn tag_string(&self, tag: &str) -> Result<Option<String>> {
+        let tag = CString::new(tag)?;
+        unsafe {
+            error::with_gerror(|err| {
+                take_string(gexiv2_metadata_try_get_tag_string(
+                    self.raw,
+                    tag.as_ptr(),
+                    err,
+                ))
+            })
+        }
+    }
+
+    /// The value of the given tag formatted for display, or `None` if it is not set.
+    #[cfg(gexiv2_0_14)]
+    pub fn tag_interpreted_string(&self, tag: &str) -> Result<Option<String>> {
+        let tag = CString::new(tag)?;
+        unsafe {
+            error::with_gerror(|err| {
+                take_string(gexiv2_metadata_try_get_tag_interpreted_string(
+                    self.raw,
+                    tag.as_ptr(),
+                    err,
+                ))
+            })
+        }
+    }
+
+    /// All values of the given tag, which is empty if it is not set.
+    #[cfg(gexiv2_0_14)]
+    pub fn tag_multiple(&self, tag: &str) -> Result<Vec<String>> {
+        let tag = CString::new(tag)?;
+        unsafe {
+            error::with_gerror(|err| {
+                GStrv::from_raw(gexiv2_metadata_try_get_tag_multiple(
+                    self.raw,
+                    tag.as_ptr(),
+                    err,
+                ))
+                .collect()
+            })
+        }
+    }
+
+    /// The raw handle, for calling functions this wrapper does not cover.
+    ///
+    /// The handle remains owned by this `Metadata` and must not be freed.
+    pub fn as_ptr(&self) -> *mut GExiv2Metadata {
+        self.raw
+    }
+
+    /// Give up ownership of the raw handle, which the caller must then free.
+    pub fn into_raw(self) -> *mut GExiv2Metadata {
+        let raw = self.raw;
+        mem::forget(self);
+        raw
+    }
+}
+
+impl Drop for Metadata {
+    fn drop(&mut self) {
+        unsafe { gexiv2_metadata_free(self.raw) }
+    }
+}
+
+/// Run a gexiv2 call that signals failure by returning something other than 1.
+fn check<F: FnOnce(*mut *mut GError) -> c_int>(call: F) -> Result<()> {
+    match unsafe { error::with_gerror(call) }? {
+        1 => Ok(()),
+        _ => Err(Error::other("gexiv2 reported failure without details")),
+    }
+}
+
+fn path_to_cstring(path: &Path) -> Result<CString> {
+    match path.to_str() {
+        Some(path) => Ok(CString::new(path)?),
+        None => Err(Error::other("path is not valid UTF-8")),
+    }
+}
+
+/// Copy a borrowed, possibly null, C string into an owned `String`.
+unsafe fn to_string(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+    }
+}
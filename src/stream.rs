@@ -0,0 +1,214 @@
+// Copyright © 2015–2022 Felix A. Crux <felixc@felixcrux.com> and contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Adapter for handing Rust streams to gexiv2 as managed streams.
+//!
+//! gexiv2 reads from a managed stream through the function pointers in a
+//! [`GExiv2ManagedStreamCallbacks`](../struct.GExiv2ManagedStreamCallbacks.html)
+//! structure. [`ManagedStream`](struct.ManagedStream.html) fills one in for any
+//! `Read + Seek` type, so metadata can be parsed without first copying the
+//! whole file into memory. Streams that are also `Write` can be wrapped with
+//! [`ManagedStream::writable()`](struct.ManagedStream.html#method.writable),
+//! so modified metadata can be saved back into them as well:
+//!
+//! ```no_run
+//! # extern crate gexiv2_sys as gexiv2;
+//! # use std::{fs, ptr};
+//! let file = fs::File::open("photo.jpg").unwrap();
+//! let mut stream = gexiv2::stream::ManagedStream::new(file);
+//! unsafe {
+//!     let meta = gexiv2::gexiv2_metadata_new();
+//!     let mut err: *mut gexiv2::GError = ptr::null_mut();
+//!     gexiv2::gexiv2_metadata_open_stream(meta, stream.as_mut_ptr(), &mut err);
+//!     // ... use `meta`, then free it before dropping `stream`.
+//!     gexiv2::gexiv2_metadata_free(meta);
+//! }
+//! ```
+
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::slice;
+
+use super::libc::{c_int, c_void};
+use super::{
+    GExiv2ManagedStreamCallbacks, GExiv2StreamCanWrite, GExiv2StreamFlush, GExiv2StreamWrite,
+    WrapperSeekOrigin,
+};
+
+/// A Rust stream together with the callback structure gexiv2 uses to access it.
+///
+/// gexiv2 holds on to the callback structure for as long as the metadata
+/// object opened from it is alive, so the `ManagedStream` must outlive that
+/// [`GExiv2Metadata`](../enum.GExiv2Metadata.html).
+pub struct ManagedStream<S> {
+    callbacks: Box<GExiv2ManagedStreamCallbacks>,
+    stream: Box<S>,
+}
+
+impl<S: Read + Seek> ManagedStream<S> {
+    /// Wrap a readable, seekable stream. gexiv2 will see it as read-only.
+    pub fn new(stream: S) -> ManagedStream<S> {
+        ManagedStream::with_callbacks(stream, unsupported, write_unsupported, flush_unsupported)
+    }
+
+    fn with_callbacks(
+        stream: S,
+        can_write: GExiv2StreamCanWrite,
+        write: GExiv2StreamWrite,
+        flush: GExiv2StreamFlush,
+    ) -> ManagedStream<S> {
+        let mut stream = Box::new(stream);
+        let callbacks = Box::new(GExiv2ManagedStreamCallbacks {
+            handle: &mut *stream as *mut S as *mut c_void,
+            can_seek: supported,
+            can_read: supported,
+            can_write,
+            length: length::<S>,
+            position: position::<S>,
+            read: read::<S>,
+            write,
+            seek: seek::<S>,
+            flush,
+        });
+        ManagedStream { callbacks, stream }
+    }
+}
+
+impl<S: Read + Write + Seek> ManagedStream<S> {
+    /// Wrap a readable, writable, seekable stream, so that gexiv2 can also save into it.
+    ///
+    /// gexiv2 has no way to truncate the stream, so if saving makes the file shorter, any
+    /// bytes past its new end are left in place.
+    pub fn writable(stream: S) -> ManagedStream<S> {
+        ManagedStream::with_callbacks(stream, supported, write::<S>, flush::<S>)
+    }
+}
+
+impl<S> ManagedStream<S> {
+    /// Pointer to the callback structure, suitable for passing to gexiv2.
+    pub fn as_mut_ptr(&mut self) -> *mut GExiv2ManagedStreamCallbacks {
+        &mut *self.callbacks
+    }
+
+    /// Borrow the underlying stream.
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Mutably borrow the underlying stream.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /// Unwrap the underlying stream.
+    pub fn into_inner(self) -> S {
+        *self.stream
+    }
+}
+
+unsafe fn stream<'a, S>(handle: *mut c_void) -> &'a mut S {
+    &mut *(handle as *mut S)
+}
+
+extern "C" fn supported(_handle: *mut c_void) -> c_int {
+    1
+}
+
+extern "C" fn unsupported(_handle: *mut c_void) -> c_int {
+    0
+}
+
+extern "C" fn length<S: Seek>(handle: *mut c_void) -> i64 {
+    let stream = unsafe { stream::<S>(handle) };
+    let len = stream.stream_position().and_then(|pos| {
+        let end = stream.seek(SeekFrom::End(0))?;
+        stream.seek(SeekFrom::Start(pos))?;
+        Ok(end)
+    });
+    len.map_or(-1, |len| len as i64)
+}
+
+extern "C" fn position<S: Seek>(handle: *mut c_void) -> i64 {
+    let stream = unsafe { stream::<S>(handle) };
+    stream.stream_position().map_or(-1, |pos| pos as i64)
+}
+
+extern "C" fn seek<S: Seek>(handle: *mut c_void, offset: i64, origin: WrapperSeekOrigin) {
+    let stream = unsafe { stream::<S>(handle) };
+    let target = match origin {
+        WrapperSeekOrigin::Begin if offset < 0 => return,
+        WrapperSeekOrigin::Begin => SeekFrom::Start(offset as u64),
+        WrapperSeekOrigin::Current => SeekFrom::Current(offset),
+        WrapperSeekOrigin::End => SeekFrom::End(offset),
+    };
+    // gexiv2 has no way to learn about a failed seek; it will notice via `position` instead.
+    let _ = stream.seek(target);
+}
+
+extern "C" fn read<S: Read>(
+    handle: *mut c_void,
+    buffer: *mut c_void,
+    offset: i32,
+    count: i32,
+) -> i32 {
+    if buffer.is_null() || offset < 0 || count <= 0 {
+        return 0;
+    }
+    let stream = unsafe { stream::<S>(handle) };
+    let buf = unsafe {
+        slice::from_raw_parts_mut((buffer as *mut u8).offset(offset as isize), count as usize)
+    };
+
+    let mut total = 0;
+    while total < buf.len() {
+        match stream.read(&mut buf[total..]) {
+            Ok(0) => break,
+            Ok(n) => total += n,
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(_) if total > 0 => break,
+            Err(_) => return -1,
+        }
+    }
+    total as i32
+}
+
+extern "C" fn write<S: Write>(handle: *mut c_void, buffer: *mut c_void, offset: i32, count: i32) {
+    if buffer.is_null() || offset < 0 || count <= 0 {
+        return;
+    }
+    let stream = unsafe { stream::<S>(handle) };
+    let buf = unsafe {
+        slice::from_raw_parts(
+            (buffer as *const u8).offset(offset as isize),
+            count as usize,
+        )
+    };
+    // As with seeking, gexiv2 can only notice a failed write through `position`.
+    let _ = stream.write_all(buf);
+}
+
+extern "C" fn flush<S: Write>(handle: *mut c_void) {
+    let stream = unsafe { stream::<S>(handle) };
+    let _ = stream.flush();
+}
+
+extern "C" fn write_unsupported(
+    _handle: *mut c_void,
+    _buffer: *mut c_void,
+    _offset: i32,
+    _count: i32,
+) {
+}
+
+extern "C" fn flush_unsupported(_handle: *mut c_void) {}
@@ -0,0 +1,99 @@
+// Copyright © 2015–2022 Felix A. Crux <felixc@felixcrux.com> and contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Helpers for taking ownership of strings and string vectors returned by gexiv2.
+//!
+//! The documentation of each function in the crate root notes whether what it
+//! returns is owned by the caller. Owned strings must be released with
+//! [`g_free()`](../fn.g_free.html) and owned string vectors with
+//! [`g_strfreev()`](../fn.g_strfreev.html); the helpers here copy the contents
+//! into Rust `String`s and do that for you.
+
+use std::ffi::CStr;
+use std::iter::FusedIterator;
+
+use super::libc::{c_char, c_void};
+use super::{g_free, g_strfreev};
+
+/// An owned, null-terminated vector of C strings, yielding its contents as `String`s.
+///
+/// The vector is freed with [`g_strfreev()`](../fn.g_strfreev.html) when this is dropped.
+#[derive(Debug)]
+pub struct GStrv {
+    ptr: *mut *mut c_char,
+    next: usize,
+}
+
+impl GStrv {
+    /// Take ownership of a string vector, such as the one returned by
+    /// [`gexiv2_metadata_get_exif_tags()`](../fn.gexiv2_metadata_get_exif_tags.html).
+    ///
+    /// A null pointer is treated as an empty vector.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be null, or a null-terminated array of valid C strings that the caller owns
+    /// and that was allocated by GLib. It must not be used after this call.
+    pub unsafe fn from_raw(ptr: *mut *mut c_char) -> GStrv {
+        GStrv { ptr, next: 0 }
+    }
+}
+
+impl Iterator for GStrv {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        if self.ptr.is_null() {
+            return None;
+        }
+        let item = unsafe { *self.ptr.add(self.next) };
+        if item.is_null() {
+            return None;
+        }
+        self.next += 1;
+        Some(
+            unsafe { CStr::from_ptr(item) }
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+}
+
+impl FusedIterator for GStrv {}
+
+impl Drop for GStrv {
+    fn drop(&mut self) {
+        if !self.ptr.is_null() {
+            unsafe { g_strfreev(self.ptr) }
+        }
+    }
+}
+
+/// Take ownership of a string returned by gexiv2, copying it into a `String` and freeing it.
+///
+/// Returns `None` if `ptr` is null.
+///
+/// # Safety
+///
+/// `ptr` must be null, or a valid C string that the caller owns and that was allocated by GLib.
+/// It must not be used after this call.
+pub unsafe fn take_string(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    let string = CStr::from_ptr(ptr).to_string_lossy().into_owned();
+    g_free(ptr as *mut c_void);
+    Some(string)
+}
@@ -0,0 +1,295 @@
+// Copyright © 2015–2022 Felix A. Crux <felixc@felixcrux.com> and contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Validated tag names.
+//!
+//! gexiv2 takes tag names as C strings like `"Exif.Image.ImageDescription"`,
+//! and only reports a malformed one when it is used. A
+//! [`TagKey`](struct.TagKey.html) is checked when it is created instead, and
+//! keeps the NUL-terminated form around to pass to any number of calls:
+//!
+//! ```no_run
+//! # extern crate gexiv2_sys as gexiv2;
+//! use gexiv2::tag::{Family, TagKey};
+//!
+//! let key: TagKey = "Xmp.iptcExt.LocationShown[1]/Iptc4xmpExt:City".parse().unwrap();
+//! assert_eq!(key.family(), Family::Xmp);
+//! assert_eq!(key.group(), "iptcExt");
+//! assert_eq!(key.name(), "LocationShown[1]/Iptc4xmpExt:City");
+//! let is_xmp = unsafe { gexiv2::gexiv2_metadata_is_xmp_tag(key.as_ptr()) };
+//! ```
+//!
+//! Keys have the form `Family.Group.Name`. For Exif and IPTC, the group and
+//! name are plain identifiers. For XMP, the group is the namespace prefix and
+//! the name a property path: `/`-separated steps, each a property name that is
+//! optionally qualified with the prefix of its own namespace, and followed by
+//! any number of array item selectors. Those are an index counting from 1,
+//! `[last()]`, a qualifier like `[?xml:lang]`, or a field or qualifier with a
+//! value like `[stEvt:action="saved"]`. A step after the first can also select
+//! a qualifier, like the `?xml:lang` in `Xmp.dc.title[1]/?xml:lang`. Only the
+//! syntax is checked, not whether Exiv2 knows the tag.
+
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::str::FromStr;
+
+use super::error::Error;
+use super::libc::{c_char, c_int};
+use super::*;
+
+/// The kind of metadata a tag belongs to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Family {
+    Exif,
+    Iptc,
+    Xmp,
+}
+
+impl Family {
+    /// The name of the family as it appears at the start of tag names.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Family::Exif => "Exif",
+            Family::Iptc => "Iptc",
+            Family::Xmp => "Xmp",
+        }
+    }
+}
+
+impl fmt::Display for Family {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A syntactically valid tag name, like `Exif.Image.ImageDescription` or `Xmp.dc.title[1]`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct TagKey {
+    key: CString,
+    family: Family,
+    /// Byte offset of the name, just past the second dot.
+    name_start: usize,
+}
+
+impl TagKey {
+    /// Parse and validate a tag name.
+    ///
+    /// The family is determined by `gexiv2_metadata_is_exif_tag()` and its IPTC and
+    /// XMP counterparts, as gexiv2 itself would.
+    pub fn new(key: &str) -> Result<TagKey, Error> {
+        let invalid = |why: &str| Error::other(format!("invalid tag name {:?}: {}", key, why));
+        let c_key = CString::new(key)?;
+
+        let classifiers: [(Family, unsafe extern "C" fn(*const c_char) -> c_int); 3] = [
+            (Family::Exif, gexiv2_metadata_is_exif_tag),
+            (Family::Iptc, gexiv2_metadata_is_iptc_tag),
+            (Family::Xmp, gexiv2_metadata_is_xmp_tag),
+        ];
+        let family = classifiers
+            .iter()
+            .find(|&&(_, is_family)| unsafe { is_family(c_key.as_ptr()) } != 0)
+            .map(|&(family, _)| family)
+            .ok_or_else(|| invalid("it does not start with Exif., Iptc. or Xmp."))?;
+
+        let rest = key
+            .strip_prefix(family.as_str())
+            .and_then(|rest| rest.strip_prefix('.'))
+            .ok_or_else(|| invalid("the family is not followed by a dot"))?;
+        let (group, name) = rest
+            .split_once('.')
+            .ok_or_else(|| invalid("it has no name after the group"))?;
+
+        match family {
+            Family::Exif | Family::Iptc => {
+                if !is_identifier(group) {
+                    return Err(invalid("the group is not an identifier"));
+                }
+                if !is_identifier(name) {
+                    return Err(invalid("the name is not an identifier"));
+                }
+            }
+            Family::Xmp => {
+                if !is_xml_name(group) {
+                    return Err(invalid("the namespace prefix is not an XML name"));
+                }
+                check_xmp_path(name).map_err(invalid)?;
+            }
+        }
+
+        Ok(TagKey {
+            key: c_key,
+            family,
+            name_start: key.len() - name.len(),
+        })
+    }
+
+    /// The family the tag belongs to.
+    pub fn family(&self) -> Family {
+        self.family
+    }
+
+    /// The group of an Exif or IPTC tag, or the namespace prefix of an XMP one.
+    pub fn group(&self) -> &str {
+        &self.as_str()[self.family.as_str().len() + 1..self.name_start - 1]
+    }
+
+    /// The name of an Exif or IPTC tag, or the property path of an XMP one.
+    pub fn name(&self) -> &str {
+        &self.as_str()[self.name_start..]
+    }
+
+    /// The whole tag name.
+    pub fn as_str(&self) -> &str {
+        self.key.to_str().expect("tag names are built from a &str")
+    }
+
+    /// The whole tag name, NUL-terminated.
+    pub fn as_c_str(&self) -> &CStr {
+        &self.key
+    }
+
+    /// The whole tag name, NUL-terminated, for passing to gexiv2.
+    ///
+    /// The pointer is valid for as long as this `TagKey` is.
+    pub fn as_ptr(&self) -> *const c_char {
+        self.key.as_ptr()
+    }
+}
+
+impl FromStr for TagKey {
+    type Err = Error;
+
+    fn from_str(key: &str) -> Result<TagKey, Error> {
+        TagKey::new(key)
+    }
+}
+
+impl fmt::Display for TagKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl AsRef<str> for TagKey {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<CStr> for TagKey {
+    fn as_ref(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+/// Whether `s` is a non-empty run of ASCII letters, digits and underscores, as Exif and IPTC
+/// group and tag names are (unknown Exif tags are named like `0x9a01`).
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+/// Whether `s` is an XML name without a colon, restricted to ASCII.
+fn is_xml_name(s: &str) -> bool {
+    let mut bytes = s.bytes();
+    match bytes.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == b'_' => {}
+        _ => return false,
+    }
+    bytes.all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-' || b == b'.')
+}
+
+/// Whether `s` is an XML name, optionally qualified with a namespace prefix like `stEvt:action`.
+fn is_qualified_name(s: &str) -> bool {
+    match s.split_once(':') {
+        Some((prefix, local)) => is_xml_name(prefix) && is_xml_name(local),
+        None => is_xml_name(s),
+    }
+}
+
+/// Check an XMP property path, like `LocationShown[1]/Iptc4xmpExt:City`.
+fn check_xmp_path(path: &str) -> Result<(), &'static str> {
+    let mut rest = path;
+    let mut first = true;
+    loop {
+        let qualifier = !first && (rest.starts_with('?') || rest.starts_with('@'));
+        let step = if qualifier { &rest[1..] } else { rest };
+        let end = step.find(&['[', '/'][..]).unwrap_or(step.len());
+        let (name, mut selectors) = step.split_at(end);
+        let valid_name = if first {
+            is_xml_name(name)
+        } else {
+            is_qualified_name(name)
+        };
+        if !valid_name {
+            return Err("a step of the property path is not a property name");
+        }
+
+        while let Some(selector) = selectors.strip_prefix('[') {
+            let close = selector_end(selector).ok_or("a '[' in the property path is not closed")?;
+            check_selector(&selector[..close])?;
+            selectors = &selector[close + 1..];
+        }
+
+        match selectors.strip_prefix('/') {
+            Some(next) => rest = next,
+            None if selectors.is_empty() => return Ok(()),
+            None => return Err("a ']' in the property path is not followed by '[' or '/'"),
+        }
+        first = false;
+    }
+}
+
+/// The index of the `]` closing a selector, skipping over any in quoted values.
+fn selector_end(selector: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in selector.char_indices() {
+        match (quote, c) {
+            (None, ']') => return Some(i),
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(open), c) if c == open => quote = None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Check the inside of an array item selector: an index counting from 1, `last()`, a
+/// qualifier like `?xml:lang`, or a field or qualifier with a value like `stEvt:action="saved"`.
+fn check_selector(selector: &str) -> Result<(), &'static str> {
+    if matches!(selector.parse::<u32>(), Ok(index) if index > 0) || selector == "last()" {
+        return Ok(());
+    }
+    let (qualifier, rest) = match selector
+        .strip_prefix('?')
+        .or_else(|| selector.strip_prefix('@'))
+    {
+        Some(rest) => (true, rest),
+        None => (false, selector),
+    };
+    let valid = match rest.split_once('=') {
+        Some((name, value)) => is_qualified_name(name) && is_quoted(value),
+        None => qualifier && is_qualified_name(rest),
+    };
+    if !valid {
+        return Err("a selector in the property path is not an index, last() or a field");
+    }
+    Ok(())
+}
+
+/// Whether `s` is a value in matching single or double quotes.
+fn is_quoted(s: &str) -> bool {
+    s.len() >= 2
+        && ((s.starts_with('"') && s.ends_with('"')) || (s.starts_with('\'') && s.ends_with('\'')))
+}
@@ -0,0 +1,68 @@
+// Copyright © 2015–2022 Felix A. Crux <felixc@felixcrux.com> and contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Names and documentation of the tags Exiv2 knows.
+//!
+//! Each tag has a constant named after it, like `EXIF_PHOTO_DATE_TIME_ORIGINAL`
+//! for `Exif.Photo.DateTimeOriginal`, so a misspelt name fails to compile
+//! rather than silently matching nothing. The constants also carry Exiv2's
+//! label, description and type for the tag, which show up in their
+//! documentation:
+//!
+//! ```no_run
+//! # extern crate gexiv2_sys as gexiv2;
+//! match gexiv2::tags::find("Exif.Photo.DateTimeOriginal") {
+//!     Some(info) => println!("{} ({}): {}", info.label, info.type_name, info.description),
+//!     None => println!("not a tag the list was generated with"),
+//! }
+//! ```
+//!
+//! The list is checked in as `src/tags/generated.rs`, and regenerated with
+//! `examples/generate_tags.rs` when Exiv2 gains tags. It reflects the Exiv2
+//! version it was generated with, which may know more or fewer tags than the
+//! one installed.
+
+mod generated;
+
+pub use self::generated::*;
+
+use super::tag::TagKey;
+
+/// What Exiv2 reports about a tag.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct TagInfo {
+    /// The full tag name, like `Exif.Image.ImageDescription`.
+    pub name: &'static str,
+    /// A short human-readable name, from `gexiv2_metadata_get_tag_label()`.
+    pub label: &'static str,
+    /// What the tag holds, from `gexiv2_metadata_get_tag_description()`.
+    pub description: &'static str,
+    /// The type of the tag's value, like `Ascii` or `XmpText`, from `gexiv2_metadata_get_tag_type()`.
+    pub type_name: &'static str,
+}
+
+impl TagInfo {
+    /// The tag name, ready to be passed to gexiv2.
+    pub fn key(&self) -> TagKey {
+        TagKey::new(self.name).expect("generated tag names are valid")
+    }
+}
+
+/// The tag with the given full name, if it is in `ALL`.
+pub fn find(name: &str) -> Option<&'static TagInfo> {
+    ALL.binary_search_by(|info| info.name.cmp(name))
+        .ok()
+        .map(|i| &ALL[i])
+}
@@ -0,0 +1,24 @@
+// Copyright © 2015–2022 Felix A. Crux <felixc@felixcrux.com> and contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+// Generated by `cargo run --example generate_tags`; see `src/tags.rs`.
+//
+// Not generated yet: the tree this module was added in had no gexiv2 to look
+// the tags up in, so the list is empty until the tool is run.
+
+use super::TagInfo;
+
+/// Every tag above, sorted by name.
+pub const ALL: &[TagInfo] = &[];
@@ -0,0 +1,66 @@
+// Copyright © 2017-2022 Felix A. Crux <felixc@felixcrux.com> and contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Tests for loading gexiv2 at runtime.
+
+use std::ffi;
+use std::ptr;
+
+use super::dlopen::{Error, Gexiv2};
+use super::*;
+
+#[test]
+fn load_and_get_version() {
+    let lib = unsafe { Gexiv2::load() }.unwrap();
+    assert!(unsafe { (lib.gexiv2_initialize)() } == 1);
+    assert!(unsafe { (lib.gexiv2_get_version)() } >= 1000);
+}
+
+#[test]
+fn open_missing_file_reports_error() {
+    let lib = unsafe { Gexiv2::load() }.unwrap();
+    let path = ffi::CString::new("/nonexistent/photo.jpg").unwrap();
+    unsafe {
+        let metadata = (lib.gexiv2_metadata_new)();
+        assert!(!metadata.is_null());
+        let mut err: *mut GError = ptr::null_mut();
+        assert_eq!(
+            (lib.gexiv2_metadata_open_path)(metadata, path.as_ptr(), &mut err),
+            0
+        );
+        assert!(!err.is_null());
+        (lib.g_error_free)(err);
+        (lib.gexiv2_metadata_free)(metadata);
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn open_other_library_lists_missing_functions() {
+    match unsafe { Gexiv2::open("libc.so.6") } {
+        Err(Error::MissingSymbols(names)) => {
+            assert!(names.iter().any(|name| name == "gexiv2_metadata_new"));
+        }
+        other => panic!("expected missing functions, got {:?}", other),
+    }
+}
+
+#[test]
+fn open_nonexistent_library_fails_to_load() {
+    match unsafe { Gexiv2::open("libgexiv2-does-not-exist.so") } {
+        Err(Error::Load(_)) => {}
+        other => panic!("expected a load error, got {:?}", other),
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright © 2015–2022 Felix A. Crux <felixc@felixcrux.com> and contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Using gexiv2 from more than one thread.
+//!
+//! Exiv2 keeps all the state of an image in the object representing it, and
+//! distinct objects can be used from different threads at the same time once
+//! the XMP toolkit has been initialized, which
+//! [`ensure_initialized()`](../fn.ensure_initialized.html) takes care of. A
+//! `GExiv2Metadata` wraps one such object, and GObject reference counting is
+//! atomic, so a handle can be moved to and freed on another thread. It is
+//! not safe to use the same handle from two threads at once, though: nothing
+//! in gexiv2 or Exiv2 guards an image against concurrent modification.
+//!
+//! [`safe::Metadata`](../safe/struct.Metadata.html) captures this: it owns a
+//! handle and is `Send`, but not `Sync`. With the `safe` feature, it is also
+//! available here as `SendableMetadata`.
+//!
+//! What Exiv2 does share between all images are a few global registries that
+//! are modified without any synchronization:
+//!
+//! * the custom XMP namespaces, changed by the `*_register_xmp_namespace` and
+//!   `*_unregister_*xmp_namespace*` functions;
+//! * the log handler and level, changed by the `gexiv2_log_set_*` functions.
+//!
+//! Changes to those must not happen concurrently with each other, or with
+//! anything that reads them, like parsing XMP that uses a custom namespace.
+//! This crate serializes its own calls that change them behind
+//! [`global_lock()`](fn.global_lock.html): the functions below and those in
+//! the [`logging`](../logging/index.html) module. Code calling the raw
+//! functions should hold it too.
+
+#[cfg(gexiv2_0_14)]
+use std::ffi::CString;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+#[cfg(gexiv2_0_14)]
+use super::*;
+
+#[cfg(gexiv2_0_14)]
+use super::error::{with_gerror, Error};
+
+/// The lock behind `global_lock()`.
+static GLOBAL_LOCK: Mutex<()> = Mutex::new(());
+
+/// Take the process-wide lock that serializes changes to Exiv2's global state.
+///
+/// The lock is not reentrant: do not call anything in this crate that takes it,
+/// like `register_xmp_namespace()` or `logging::set_handler()`, while holding it.
+pub fn global_lock() -> MutexGuard<'static, ()> {
+    GLOBAL_LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// An owned `GExiv2Metadata` handle that can be moved to another thread, but not shared.
+#[cfg(feature = "safe")]
+pub use super::safe::Metadata as SendableMetadata;
+
+/// Register a custom XMP namespace under the given prefix, while holding `global_lock()`.
+///
+/// Fails if a namespace is already registered under the prefix.
+#[cfg(gexiv2_0_14)]
+pub fn register_xmp_namespace(name: &str, prefix: &str) -> Result<(), Error> {
+    let (name, prefix) = (CString::new(name)?, CString::new(prefix)?);
+    let _lock = global_lock();
+    let registered = unsafe {
+        with_gerror(|err| {
+            gexiv2_metadata_try_register_xmp_namespace(name.as_ptr(), prefix.as_ptr(), err)
+        })
+    }?;
+    match registered {
+        1 => Ok(()),
+        _ => Err(Error::other(
+            "an XMP namespace is already registered under that prefix",
+        )),
+    }
+}
+
+/// Unregister a custom XMP namespace, while holding `global_lock()`.
+///
+/// Fails if no custom namespace of that name is registered.
+#[cfg(gexiv2_0_14)]
+pub fn unregister_xmp_namespace(name: &str) -> Result<(), Error> {
+    let name = CString::new(name)?;
+    let _lock = global_lock();
+    let unregistered = unsafe {
+        with_gerror(|err| gexiv2_metadata_try_unregister_xmp_namespace(name.as_ptr(), err))
+    }?;
+    match unregistered {
+        1 => Ok(()),
+        _ => Err(Error::other(
+            "no custom XMP namespace of that name is registered",
+        )),
+    }
+}
+
+/// Unregister all custom XMP namespaces, while holding `global_lock()`.
+#[cfg(gexiv2_0_14)]
+pub fn unregister_all_xmp_namespaces() -> Result<(), Error> {
+    let _lock = global_lock();
+    unsafe { with_gerror(|err| gexiv2_metadata_try_unregister_all_xmp_namespaces(err)) }
+}
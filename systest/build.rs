@@ -0,0 +1,154 @@
+// Copyright © 2015-2022 Felix A. Crux <felixc@felixcrux.com> and contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Generate ABI checks for every declaration in `../src/lib.rs`.
+//!
+//! ctest2 compiles a C program against the gexiv2 headers, found via the
+//! include paths gexiv2-sys's own build script passes on, that checks the size,
+//! alignment and field offsets of each type and the signature of each function.
+//! ctest2 does not check Rust enums or expand `bitflags!`, so enum and flag
+//! values are exported by `src/enums.c` instead, for `tests/enums.rs` to
+//! compare.
+
+extern crate cc;
+extern crate ctest2;
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+include!("../version_cfgs.rs");
+
+/// Features of gexiv2-sys enabled in `Cargo.toml` whose declarations ctest2 should check too.
+const FEATURES: &[&str] = &[
+    "raw-tag-access",
+    "exif-data-access",
+    "xmp-packet-access",
+    "gobject",
+];
+
+/// Functions that `src/glue.cpp` provides for versions of gexiv2 that no longer declare them.
+const GLUE_FNS: &[&str] = &["gexiv2_metadata_free", "gexiv2_preview_image_free"];
+
+fn main() {
+    let version = env::var("DEP_GEXIV2_VERSION").expect("gexiv2-sys reports the gexiv2 version");
+    let version = parse_version(&version);
+    let include_paths: Vec<PathBuf> = env::var_os("DEP_GEXIV2_INCLUDE")
+        .map(|paths| env::split_paths(&paths).collect())
+        .unwrap_or_default();
+
+    let mut enums = cc::Build::new();
+    enums.file("src/enums.c");
+    for path in &include_paths {
+        enums.include(path);
+    }
+    enums.compile("systest_enums");
+
+    let mut cfg = ctest2::TestGenerator::new();
+    cfg.header("gexiv2/gexiv2.h");
+    for path in &include_paths {
+        cfg.include(path);
+    }
+    for &(introduced, flag) in VERSION_CFGS {
+        println!("cargo:rustc-check-cfg=cfg({})", flag);
+        if version >= introduced {
+            println!("cargo:rustc-cfg={}", flag);
+            cfg.cfg(flag, None);
+        }
+    }
+    for feature in FEATURES {
+        cfg.cfg("feature", Some(feature));
+    }
+
+    // gexiv2 and GLib name every type through a typedef, and a few differently from us.
+    cfg.type_name(|ty, _is_struct, _is_union| match ty {
+        "Orientation" => "GExiv2Orientation".to_string(),
+        "GExiv2ManagedStreamCallbacks" => "ManagedStreamCallbacks".to_string(),
+        ty if ty.starts_with("GExiv2Stream") => format!("Stream_{}", &ty["GExiv2Stream".len()..]),
+        ty => ty.to_string(),
+    });
+    cfg.field_name(|s, field| match (s, field) {
+        ("GExiv2ManagedStreamCallbacks", "handle") => field.to_string(),
+        ("GExiv2ManagedStreamCallbacks", _) => pascal_case(field),
+        _ => field.to_string(),
+    });
+    // GLib declares the message non-const, but it must not be modified through a `GError`.
+    cfg.skip_field_type(|s, field| s == "GError" && field == "message");
+    // The flags are a Rust struct that is never passed to C; `tests/enums.rs` checks their values.
+    cfg.skip_type(|name| name == "GExiv2XmpFormatFlags");
+    cfg.skip_fn(move |name| version >= (0, 15) && GLUE_FNS.contains(&name));
+
+    cfg.generate(declarations_only(), "all.rs");
+}
+
+/// Write out a copy of `../src/lib.rs` without its `mod` declarations.
+///
+/// The helper modules hold generic and non-`repr(C)` Rust types, which ctest2 rejects.
+/// ctest2 cannot expand the `bitflags!` invocation either, so it is replaced by an alias
+/// that lets the functions taking the flags be checked.
+fn declarations_only() -> PathBuf {
+    let lib = Path::new("../src/lib.rs");
+    println!("cargo:rerun-if-changed={}", lib.display());
+    let source = fs::read_to_string(lib).expect("gexiv2-sys source is readable");
+
+    let mut declarations = String::new();
+    let mut attrs = String::new();
+    let mut in_bitflags = false;
+    for line in source.lines() {
+        let item = line.trim_start();
+        if in_bitflags {
+            in_bitflags = line != "}";
+        } else if item.starts_with("bitflags! {") {
+            declarations.push_str(&attrs);
+            declarations.push_str("pub type GExiv2XmpFormatFlags = libc::c_uint;\n");
+            attrs.clear();
+            in_bitflags = true;
+        } else if item.starts_with("#[") {
+            attrs.push_str(line);
+            attrs.push('\n');
+        } else if item.starts_with("mod ") || item.starts_with("pub mod ") {
+            attrs.clear();
+        } else {
+            declarations.push_str(&attrs);
+            declarations.push_str(line);
+            declarations.push('\n');
+            attrs.clear();
+        }
+    }
+
+    let path = PathBuf::from(env::var_os("OUT_DIR").unwrap()).join("gexiv2_sys.rs");
+    fs::write(&path, declarations).expect("OUT_DIR is writable");
+    path
+}
+
+/// Extract the major and minor components from a version string like "0.14.2".
+fn parse_version(version: &str) -> (u32, u32) {
+    let mut parts = version.split('.').map(|part| part.parse().unwrap_or(0));
+    (parts.next().unwrap_or(0), parts.next().unwrap_or(0))
+}
+
+/// Convert a snake_case field name, like `can_seek`, to PascalCase, like `CanSeek`.
+fn pascal_case(name: &str) -> String {
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<String>>()
+        .concat()
+}
@@ -0,0 +1,91 @@
+#include <string.h>
+#include <gexiv2/gexiv2.h>
+
+// Values and sizes of the C enums that gexiv2-sys mirrors as Rust enums (or bitflags), keyed
+// by the Rust names, since ctest2 cannot check those itself.
+
+struct named_value {
+  const char *name;
+  long value;
+};
+
+struct named_size {
+  const char *name;
+  size_t size;
+};
+
+static const struct named_value VALUES[] = {
+  { "Orientation::Unspecified", GEXIV2_ORIENTATION_UNSPECIFIED },
+  { "Orientation::Normal", GEXIV2_ORIENTATION_NORMAL },
+  { "Orientation::HorizontalFlip", GEXIV2_ORIENTATION_HFLIP },
+  { "Orientation::Rotate180", GEXIV2_ORIENTATION_ROT_180 },
+  { "Orientation::VerticalFlip", GEXIV2_ORIENTATION_VFLIP },
+  { "Orientation::Rotate90HorizontalFlip", GEXIV2_ORIENTATION_ROT_90_HFLIP },
+  { "Orientation::Rotate90", GEXIV2_ORIENTATION_ROT_90 },
+  { "Orientation::Rotate90VerticalFlip", GEXIV2_ORIENTATION_ROT_90_VFLIP },
+  { "Orientation::Rotate270", GEXIV2_ORIENTATION_ROT_270 },
+
+  { "GExiv2LogLevel::DEBUG", GEXIV2_LOG_LEVEL_DEBUG },
+  { "GExiv2LogLevel::INFO", GEXIV2_LOG_LEVEL_INFO },
+  { "GExiv2LogLevel::WARN", GEXIV2_LOG_LEVEL_WARNING },
+  { "GExiv2LogLevel::ERROR", GEXIV2_LOG_LEVEL_ERROR },
+  { "GExiv2LogLevel::MUTE", GEXIV2_LOG_LEVEL_MUTE },
+
+  { "GExiv2StructureType::None", GEXIV2_STRUCTURE_XA_NONE },
+  { "GExiv2StructureType::Alt", GEXIV2_STRUCTURE_XA_ALT },
+  { "GExiv2StructureType::Bag", GEXIV2_STRUCTURE_XA_BAG },
+  { "GExiv2StructureType::Seq", GEXIV2_STRUCTURE_XA_SEQ },
+  { "GExiv2StructureType::Lang", GEXIV2_STRUCTURE_XA_LANG },
+
+  { "WrapperSeekOrigin::Begin", Begin },
+  { "WrapperSeekOrigin::Current", Current },
+  { "WrapperSeekOrigin::End", End },
+
+  { "GExiv2XmpFormatFlags::OMIT_PACKET_WRAPPER", GEXIV2_OMIT_PACKET_WRAPPER },
+  { "GExiv2XmpFormatFlags::READ_ONLY_PACKET", GEXIV2_READ_ONLY_PACKET },
+  { "GExiv2XmpFormatFlags::USE_COMPACT_FORMAT", GEXIV2_USE_COMPACT_FORMAT },
+  { "GExiv2XmpFormatFlags::INCLUDE_THUMBNAIL_PAD", GEXIV2_INCLUDE_THUMBNAIL_PAD },
+  { "GExiv2XmpFormatFlags::EXACT_PACKET_LENGTH", GEXIV2_EXACT_PACKET_LENGTH },
+  { "GExiv2XmpFormatFlags::WRITE_ALIAS_COMMENTS", GEXIV2_WRITE_ALIAS_COMMENTS },
+  { "GExiv2XmpFormatFlags::OMIT_ALL_FORMATTING", GEXIV2_OMIT_ALL_FORMATTING },
+
+#ifdef GEXIV2_CHECK_VERSION
+#if GEXIV2_CHECK_VERSION(0, 14, 0)
+  { "GExiv2ByteOrder::Little", GEXIV2_BYTE_ORDER_LITTLE },
+  { "GExiv2ByteOrder::Big", GEXIV2_BYTE_ORDER_BIG },
+#endif
+#endif
+};
+
+static const struct named_size SIZES[] = {
+  { "Orientation", sizeof(GExiv2Orientation) },
+  { "GExiv2LogLevel", sizeof(GExiv2LogLevel) },
+  { "GExiv2StructureType", sizeof(GExiv2StructureType) },
+  { "WrapperSeekOrigin", sizeof(WrapperSeekOrigin) },
+#ifdef GEXIV2_CHECK_VERSION
+#if GEXIV2_CHECK_VERSION(0, 14, 0)
+  { "GExiv2ByteOrder", sizeof(GExiv2ByteOrder) },
+#endif
+#endif
+};
+
+int systest_enum_value(const char *name, long *value) {
+  size_t i;
+  for (i = 0; i < sizeof(VALUES) / sizeof(VALUES[0]); i++) {
+    if (strcmp(VALUES[i].name, name) == 0) {
+      *value = VALUES[i].value;
+      return 1;
+    }
+  }
+  return 0;
+}
+
+size_t systest_enum_size(const char *name) {
+  size_t i;
+  for (i = 0; i < sizeof(SIZES) / sizeof(SIZES[0]); i++) {
+    if (strcmp(SIZES[i].name, name) == 0) {
+      return SIZES[i].size;
+    }
+  }
+  return 0;
+}
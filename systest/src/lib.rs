@@ -0,0 +1,31 @@
+// Copyright © 2015-2022 Felix A. Crux <felixc@felixcrux.com> and contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! ABI checks of the gexiv2-sys declarations against the installed gexiv2 headers.
+//!
+//! Run them with `cargo test` in this directory. The checks themselves live in
+//! `tests/`; this library only declares the helpers from `src/enums.c`.
+
+extern crate libc;
+
+use libc::{c_char, c_int, c_long, size_t};
+
+extern "C" {
+    /// Look up the value of a C enumerator by the name of its Rust counterpart, like
+    /// `Orientation::Normal`. Returns 0 if the name is unknown.
+    pub fn systest_enum_value(name: *const c_char, value: *mut c_long) -> c_int;
+    /// Size of a C enum type, by the name of its Rust counterpart. Returns 0 if unknown.
+    pub fn systest_enum_size(name: *const c_char) -> size_t;
+}
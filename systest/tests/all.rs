@@ -0,0 +1,26 @@
+// Copyright © 2015-2022 Felix A. Crux <felixc@felixcrux.com> and contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! The checks generated by ctest2 from `../src/lib.rs`; see `build.rs`.
+
+#![allow(warnings, clippy::all)]
+
+extern crate gexiv2_sys;
+extern crate libc;
+
+use gexiv2_sys::*;
+use libc::*;
+
+include!(concat!(env!("OUT_DIR"), "/all.rs"));
@@ -0,0 +1,166 @@
+// Copyright © 2015-2022 Felix A. Crux <felixc@felixcrux.com> and contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Checks that the Rust enums and flags match their C counterparts.
+
+extern crate gexiv2_sys;
+extern crate libc;
+extern crate systest;
+
+use std::ffi::CString;
+use std::mem::size_of;
+
+use gexiv2_sys::*;
+
+fn check_values<T>(type_name: &str, values: &[(&str, i64)]) {
+    let name = CString::new(type_name).unwrap();
+    assert_eq!(
+        unsafe { systest::systest_enum_size(name.as_ptr()) },
+        size_of::<T>(),
+        "size of {}",
+        type_name
+    );
+    check_flags(type_name, values);
+}
+
+fn check_flags(type_name: &str, values: &[(&str, i64)]) {
+    for &(variant, rust_value) in values {
+        let name = format!("{}::{}", type_name, variant);
+        let c_name = CString::new(name.as_str()).unwrap();
+        let mut c_value: libc::c_long = 0;
+        assert_eq!(
+            unsafe { systest::systest_enum_value(c_name.as_ptr(), &mut c_value) },
+            1,
+            "{} is not known to the C side",
+            name
+        );
+        assert_eq!(rust_value, c_value as i64, "value of {}", name);
+    }
+}
+
+#[test]
+fn orientation() {
+    check_values::<Orientation>(
+        "Orientation",
+        &[
+            ("Unspecified", Orientation::Unspecified as i64),
+            ("Normal", Orientation::Normal as i64),
+            ("HorizontalFlip", Orientation::HorizontalFlip as i64),
+            ("Rotate180", Orientation::Rotate180 as i64),
+            ("VerticalFlip", Orientation::VerticalFlip as i64),
+            (
+                "Rotate90HorizontalFlip",
+                Orientation::Rotate90HorizontalFlip as i64,
+            ),
+            ("Rotate90", Orientation::Rotate90 as i64),
+            (
+                "Rotate90VerticalFlip",
+                Orientation::Rotate90VerticalFlip as i64,
+            ),
+            ("Rotate270", Orientation::Rotate270 as i64),
+        ],
+    );
+}
+
+#[test]
+fn log_level() {
+    check_values::<GExiv2LogLevel>(
+        "GExiv2LogLevel",
+        &[
+            ("DEBUG", GExiv2LogLevel::DEBUG as i64),
+            ("INFO", GExiv2LogLevel::INFO as i64),
+            ("WARN", GExiv2LogLevel::WARN as i64),
+            ("ERROR", GExiv2LogLevel::ERROR as i64),
+            ("MUTE", GExiv2LogLevel::MUTE as i64),
+        ],
+    );
+}
+
+#[test]
+fn structure_type() {
+    check_values::<GExiv2StructureType>(
+        "GExiv2StructureType",
+        &[
+            ("None", GExiv2StructureType::None as i64),
+            ("Alt", GExiv2StructureType::Alt as i64),
+            ("Bag", GExiv2StructureType::Bag as i64),
+            ("Seq", GExiv2StructureType::Seq as i64),
+            ("Lang", GExiv2StructureType::Lang as i64),
+        ],
+    );
+}
+
+#[test]
+fn seek_origin() {
+    check_values::<WrapperSeekOrigin>(
+        "WrapperSeekOrigin",
+        &[
+            ("Begin", WrapperSeekOrigin::Begin as i64),
+            ("Current", WrapperSeekOrigin::Current as i64),
+            ("End", WrapperSeekOrigin::End as i64),
+        ],
+    );
+}
+
+#[cfg(gexiv2_0_14)]
+#[test]
+fn byte_order() {
+    check_values::<GExiv2ByteOrder>(
+        "GExiv2ByteOrder",
+        &[
+            ("Little", GExiv2ByteOrder::Little as i64),
+            ("Big", GExiv2ByteOrder::Big as i64),
+        ],
+    );
+}
+
+// Passed to gexiv2 as an integer rather than as the C enum, so only the values need to match.
+#[test]
+fn xmp_format_flags() {
+    let flag = |flag: GExiv2XmpFormatFlags| flag.bits() as i64;
+    check_flags(
+        "GExiv2XmpFormatFlags",
+        &[
+            (
+                "OMIT_PACKET_WRAPPER",
+                flag(GExiv2XmpFormatFlags::OMIT_PACKET_WRAPPER),
+            ),
+            (
+                "READ_ONLY_PACKET",
+                flag(GExiv2XmpFormatFlags::READ_ONLY_PACKET),
+            ),
+            (
+                "USE_COMPACT_FORMAT",
+                flag(GExiv2XmpFormatFlags::USE_COMPACT_FORMAT),
+            ),
+            (
+                "INCLUDE_THUMBNAIL_PAD",
+                flag(GExiv2XmpFormatFlags::INCLUDE_THUMBNAIL_PAD),
+            ),
+            (
+                "EXACT_PACKET_LENGTH",
+                flag(GExiv2XmpFormatFlags::EXACT_PACKET_LENGTH),
+            ),
+            (
+                "WRITE_ALIAS_COMMENTS",
+                flag(GExiv2XmpFormatFlags::WRITE_ALIAS_COMMENTS),
+            ),
+            (
+                "OMIT_ALL_FORMATTING",
+                flag(GExiv2XmpFormatFlags::OMIT_ALL_FORMATTING),
+            ),
+        ],
+    );
+}
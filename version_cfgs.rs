@@ -0,0 +1,25 @@
+// Copyright © 2015-2022 Felix A. Crux <felixc@felixcrux.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+// Shared between `build.rs` and `systest/build.rs` with `include!`.
+
+/// Versions that introduced declarations we gate on, with the cfg flag each one sets.
+///
+/// The 0.15 development series already carries the 0.16 API break, so it counts as 0.16.
+const VERSION_CFGS: &[((u32, u32), &str)] = &[
+    ((0, 12), "gexiv2_0_12"),
+    ((0, 14), "gexiv2_0_14"),
+    ((0, 15), "gexiv2_0_16"),
+];